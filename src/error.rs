@@ -31,10 +31,22 @@ pub enum Error {
     #[error("Bech32 Error: {0}")]
     Bech32Hrp(#[from] bech32::primitives::hrp::Error),
 
+    /// Blossom issue
+    #[error("Blossom Error: {0}")]
+    Blossom(String),
+
+    /// Calendar issue
+    #[error("Calendar Error: {0}")]
+    Calendar(String),
+
     /// Crypto error
     #[error("Crypto Error: {0}")]
     Crypto(#[from] nip44::Error),
 
+    /// Data Vending Machine issue
+    #[error("DVM Error: {0}")]
+    Dvm(String),
+
     /// Encryption/Decryption Error
     #[error("Private Key Encryption/Decryption Error")]
     PrivateKeyEncryption,
@@ -59,6 +71,14 @@ pub enum Error {
     #[error("Formatting Error: {0}")]
     Fmt(#[from] std::fmt::Error),
 
+    /// Git (NIP-34) issue
+    #[error("Git Error: {0}")]
+    Git(String),
+
+    /// Group (NIP-29) issue
+    #[error("Group Error: {0}")]
+    Group(String),
+
     /// A hash mismatch verification error
     #[error("Hash Mismatch")]
     HashMismatch,
@@ -67,6 +87,14 @@ pub enum Error {
     #[error("Hex Decode Error: {0}")]
     HexDecode(#[from] hex::FromHexError),
 
+    /// Highlight (NIP-84) issue
+    #[error("Highlight Error: {0}")]
+    Highlight(String),
+
+    /// NIP-98 HTTP Auth issue
+    #[error("HTTP Auth Error: {0}")]
+    HttpAuth(String),
+
     /// Invalid encrypted private key
     #[error("Invalid Encrypted Private Key")]
     InvalidEncryptedPrivateKey,
@@ -91,10 +119,18 @@ pub enum Error {
     #[error("Invalid digest length")]
     InvalidLength(#[from] hmac::digest::InvalidLength),
 
+    /// Invalid metadata field
+    #[error("Invalid metadata: {0}")]
+    InvalidMetadata(String),
+
     /// Invalid NAddr
     #[error("Invalid naddr")]
     InvalidNAddr,
 
+    /// Invalid NIP-05 identifier
+    #[error("Invalid NIP-05 identifier: {0}")]
+    InvalidNip05(String),
+
     /// Invalid NEvent
     #[error("Invalid nevent")]
     InvalidNEvent,
@@ -143,6 +179,22 @@ pub enum Error {
     #[error("Missing URL Authority")]
     InvalidUrlMissingAuthority,
 
+    /// A NIP-51 list's private items must be decrypted first
+    #[error("List private items are still encrypted")]
+    ListEncrypted,
+
+    /// Live activity issue
+    #[error("Live Activity Error: {0}")]
+    LiveActivity(String),
+
+    /// Marketplace (NIP-15) issue
+    #[error("Marketplace Error: {0}")]
+    Marketplace(String),
+
+    /// NIP-96 file storage issue
+    #[error("NIP-96 Error: {0}")]
+    Nip96(String),
+
     /// Addr to a non-replaceable event kind
     #[error("Event kind is not replaceable")]
     NonReplaceableAddr,
@@ -212,6 +264,10 @@ pub enum Error {
     #[error("UTF-8 Error: {0}")]
     Utf8Error(#[from] std::str::Utf8Error),
 
+    /// Nostr Wallet Connect issue
+    #[error("Wallet Connect Error: {0}")]
+    WalletConnect(String),
+
     /// Wrong event kind
     #[error("Wrong event kind")]
     WrongEventKind,
@@ -59,15 +59,34 @@ macro_rules! test_serde_val {
 
 mod types;
 pub use types::{
-    find_nostr_bech32_pos, find_nostr_url_pos, ClientMessage, ContentEncryptionAlgorithm,
-    ContentSegment, CountResult, DelegationConditions, EncryptedPrivateKey, Event, EventDelegation,
+    find_nostr_bech32_pos, find_nostr_url_pos, normalize_wiki_topic, Article, Auction, AuctionBid,
+    AuctionBidConfirmation, AuctionBidStatus, BadgeAward, BadgeDefinition, BadgeImage, Birthday,
+    BlobDescriptor, BlossomAuth, BlossomServerList, BlossomVerb, Calendar, CalendarDate,
+    CalendarEvent, CalendarEventRsvp, CalendarEventTime, CalendarParticipant, Channel, ChannelHide,
+    ChannelInfo, ChannelMessage, ChannelMute, ChannelState, ClassifiedListing, ClientMessage,
+    CommunityDefinition, CommunityPost, CommunityPostApproval, CommunityRelay, Contact,
+    ContactList, ContactListDiff, ContactListMerge, ContentEncryptionAlgorithm, ContentSegment,
+    CountResult, CustomerOrder, DelegationConditions, EncryptedPrivateKey, Event, EventDelegation,
     EventKind, EventKindIterator, EventKindOrRange, EventReference, Fee, FileMetadata, Filter,
-    Hll8, Id, IdHex, Identity, KeySecurity, KeySigner, Metadata, MilliSatoshi, NAddr, NEvent,
-    Nip05, NostrBech32, NostrUrl, ParsedTag, PayRequestData, PreEvent, PrivateKey, Profile,
+    FreeBusy, GitCommitter, GitPatch, GitRepository, GitRepositoryState, GitStatus,
+    GitStatusUpdate, GroupAction, GroupList, GroupMetadata, GroupMetadataEdit, GroupModeration,
+    GroupState, Highlight, HighlightAuthor, HighlightSource, Hll8, HttpAuth, Id, IdHex, Identity,
+    IdentityPlatform, JobFeedback, JobInput, JobInputType, JobRequest, JobResult, JobStatus,
+    KeySecurity, KeySigner, ListingPrice, ListingStatus, LiveActivity, LiveActivityStatus,
+    LiveChatMessage, LiveParticipant, MarkdownSegment, MarketplaceMessage, Metadata, MetadataIssue,
+    MilliSatoshi, NAddr, NEvent, Nip05, Nip05Identifier, Nip96DeleteResponse, Nip96ListResponse,
+    Nip96Nip94Event, Nip96Plan, Nip96ServerInfo, Nip96Status, Nip96UploadResponse, NostrBech32,
+    NostrList, NostrUrl, NwcClient, NwcEncryption, NwcError, NwcErrorCode, NwcInfo,
+    NwcMultiPayInvoiceItem, NwcNotification, NwcRequest, NwcResponse, NwcResult, NwcTransaction,
+    NwcTransactionType, NwcUri, NwcWalletInfo, OrderContact, OrderItem, OrderStatus, ParsedTag,
+    PayRequestData, PaymentOption, PaymentRequest, Poll, PollOption, PollResponse, PollType,
+    PreEvent, PrivateKey, Product, ProductShipping, Profile, ProfileBadge, ProfileBadges,
     PublicKey, PublicKeyHex, RelayFees, RelayInformationDocument, RelayLimitation, RelayList,
-    RelayListUsage, RelayMessage, RelayOrigin, RelayRetention, RelayUrl, RelayUsage, RelayUsageSet,
-    Rumor, ShatteredContent, Signature, SignatureHex, Signer, SimpleRelayList, SimpleRelayUsage,
-    Span, SubscriptionId, Tag, UncheckedUrl, Unixtime, Url, Why, XOnlyPublicKey, ZapData,
+    RelayListUsage, RelayMessage, RelayOrigin, RelayPlanner, RelayReadPlan, RelayRetention,
+    RelayUrl, RelayUsage, RelayUsageSet, RsvpStatus, Rumor, ShatteredContent, ShatteredMarkdown,
+    ShippingZone, Signature, SignatureHex, Signer, SimpleRelayList, SimpleRelayUsage, Span, Stall,
+    SubscriptionId, Tag, UncheckedUrl, Unixtime, Url, UserStatus, UserStatusType, Why, WikiArticle,
+    WikiMergeRequest, WikiRelation, XOnlyPublicKey, ZapData, ZapSplit, ZapSplitPlan,
 };
 
 mod versioned;
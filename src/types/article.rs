@@ -0,0 +1,154 @@
+use crate::types::{
+    Event, EventKind, NAddr, PreEvent, PublicKey, ShatteredMarkdown, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+
+/// A NIP-23 long-form article (kind 30023), or a draft of one (kind 30024)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Article {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The article title
+    pub title: Option<String>,
+
+    /// An image shown with the title
+    pub image: Option<UncheckedUrl>,
+
+    /// A short summary
+    pub summary: Option<String>,
+
+    /// When the article was first published. Updates keep this unchanged.
+    pub published_at: Option<Unixtime>,
+
+    /// Hashtags
+    pub hashtags: Vec<String>,
+
+    /// The article, in markdown
+    pub content: String,
+
+    /// Whether this is a draft
+    pub draft: bool,
+}
+
+impl Article {
+    /// Create a new article
+    pub fn new(d: String, content: String) -> Article {
+        Article {
+            d,
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// The event kind of the article
+    pub fn kind(&self) -> EventKind {
+        if self.draft {
+            EventKind::DraftLongFormContent
+        } else {
+            EventKind::LongFormContent
+        }
+    }
+
+    /// The address of this article, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: self.kind(),
+            author,
+        }
+    }
+
+    /// Break the article content into segments
+    pub fn shattered_markdown(&self) -> ShatteredMarkdown {
+        ShatteredMarkdown::new(self.content.clone())
+    }
+
+    /// Parse a kind-30023 LongFormContent or kind-30024 DraftLongFormContent event
+    pub fn from_event(event: &Event) -> Result<Article, Error> {
+        let draft = match event.kind {
+            EventKind::LongFormContent => false,
+            EventKind::DraftLongFormContent => true,
+            _ => return Err(Error::WrongEventKind),
+        };
+
+        let mut article = Article {
+            content: event.content.clone(),
+            draft,
+            ..Default::default()
+        };
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => article.d = tag.value().to_owned(),
+                "title" => article.title = Some(tag.value().to_owned()),
+                "image" => article.image = Some(UncheckedUrl(tag.value().to_owned())),
+                "summary" => article.summary = Some(tag.value().to_owned()),
+                "published_at" => article.published_at = Some(Unixtime(tag.value().parse()?)),
+                "t" => article.hashtags.push(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+        Ok(article)
+    }
+
+    /// Create the kind-30023 LongFormContent (or kind-30024 DraftLongFormContent)
+    /// PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d])];
+        if let Some(title) = &self.title {
+            tags.push(Tag::new(&["title", title]));
+        }
+        if let Some(image) = &self.image {
+            tags.push(Tag::new(&["image", &image.0]));
+        }
+        if let Some(summary) = &self.summary {
+            tags.push(Tag::new(&["summary", summary]));
+        }
+        if let Some(published_at) = self.published_at {
+            tags.push(Tag::new(&["published_at", &published_at.0.to_string()]));
+        }
+        for hashtag in self.hashtags.iter() {
+            tags.push(Tag::new(&["t", hashtag]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.kind(),
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_article_round_trip() {
+        let private_key = PrivateKey::generate();
+        let mut article = Article::new(
+            "lorem-ipsum".to_owned(),
+            "# Lorem Ipsum\n\nDolor sit amet.".to_owned(),
+        );
+        article.title = Some("Lorem Ipsum".to_owned());
+        article.summary = Some("Dolor sit amet".to_owned());
+        article.published_at = Some(Unixtime(1296962229));
+        article.hashtags.push("placeholder".to_owned());
+        let event = private_key
+            .sign_event(article.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(event.kind, EventKind::LongFormContent);
+        assert_eq!(Article::from_event(&event).unwrap(), article);
+
+        article.draft = true;
+        let event = private_key
+            .sign_event(article.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(event.kind, EventKind::DraftLongFormContent);
+        assert_eq!(Article::from_event(&event).unwrap(), article);
+    }
+}
@@ -0,0 +1,393 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+
+/// A badge image, with its dimensions if known
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadgeImage {
+    /// Where the image is
+    pub url: UncheckedUrl,
+
+    /// The width and height in pixels
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl BadgeImage {
+    fn from_tag(tag: &Tag) -> BadgeImage {
+        BadgeImage {
+            url: UncheckedUrl(tag.value().to_owned()),
+            dimensions: tag.get_opt_index(2).and_then(|dim| {
+                let (w, h) = dim.split_once('x')?;
+                Some((w.parse().ok()?, h.parse().ok()?))
+            }),
+        }
+    }
+
+    fn to_tag(&self, tagname: &str) -> Tag {
+        let mut tag = Tag::new(&[tagname, &self.url.0]);
+        if let Some((w, h)) = self.dimensions {
+            tag.set_index(2, format!("{w}x{h}"));
+        }
+        tag
+    }
+}
+
+/// A NIP-58 badge definition (kind 30009)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BadgeDefinition {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// A short name
+    pub name: Option<String>,
+
+    /// What the badge is for
+    pub description: Option<String>,
+
+    /// The badge image
+    pub image: Option<BadgeImage>,
+
+    /// Smaller versions of the image
+    pub thumbs: Vec<BadgeImage>,
+}
+
+impl BadgeDefinition {
+    /// Create a new badge definition
+    pub fn new(d: String) -> BadgeDefinition {
+        BadgeDefinition {
+            d,
+            ..Default::default()
+        }
+    }
+
+    /// The address of this badge definition, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: EventKind::BadgeDefinition,
+            author,
+        }
+    }
+
+    /// Parse a kind-30009 BadgeDefinition event
+    pub fn from_event(event: &Event) -> Result<BadgeDefinition, Error> {
+        if event.kind != EventKind::BadgeDefinition {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut definition = BadgeDefinition::default();
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => definition.d = tag.value().to_owned(),
+                "name" => definition.name = Some(tag.value().to_owned()),
+                "description" => definition.description = Some(tag.value().to_owned()),
+                "image" => definition.image = Some(BadgeImage::from_tag(tag)),
+                "thumb" => definition.thumbs.push(BadgeImage::from_tag(tag)),
+                _ => {}
+            }
+        }
+        Ok(definition)
+    }
+
+    /// Create the kind-30009 BadgeDefinition PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d])];
+        if let Some(name) = &self.name {
+            tags.push(Tag::new(&["name", name]));
+        }
+        if let Some(description) = &self.description {
+            tags.push(Tag::new(&["description", description]));
+        }
+        if let Some(image) = &self.image {
+            tags.push(image.to_tag("image"));
+        }
+        for thumb in self.thumbs.iter() {
+            tags.push(thumb.to_tag("thumb"));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::BadgeDefinition,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+}
+
+/// A NIP-58 badge award (kind 8), awarding a badge to one or more people
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadgeAward {
+    /// The badge definition
+    pub badge: NAddr,
+
+    /// The people awarded the badge, with a relay where they may be found
+    pub recipients: Vec<(PublicKey, Option<UncheckedUrl>)>,
+}
+
+impl BadgeAward {
+    /// Create a new badge award with no recipients
+    pub fn new(badge: NAddr) -> BadgeAward {
+        BadgeAward {
+            badge,
+            recipients: vec![],
+        }
+    }
+
+    /// Award the badge to someone. Returns false if they are already a recipient.
+    pub fn add_recipient(&mut self, pubkey: PublicKey, relay: Option<UncheckedUrl>) -> bool {
+        if self.recipients.iter().any(|(pk, _)| *pk == pubkey) {
+            return false;
+        }
+        self.recipients.push((pubkey, relay));
+        true
+    }
+
+    /// Whether someone was awarded the badge
+    pub fn is_recipient(&self, pubkey: &PublicKey) -> bool {
+        self.recipients.iter().any(|(pk, _)| pk == pubkey)
+    }
+
+    /// Parse a kind-8 BadgeAward event
+    pub fn from_event(event: &Event) -> Result<BadgeAward, Error> {
+        if event.kind != EventKind::BadgeAward {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut badge: Option<NAddr> = None;
+        let mut recipients: Vec<(PublicKey, Option<UncheckedUrl>)> = Vec::new();
+        for tag in event.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Address { address, .. })
+                    if badge.is_none() && address.kind == EventKind::BadgeDefinition =>
+                {
+                    badge = Some(address)
+                }
+                Ok(ParsedTag::Pubkey {
+                    pubkey,
+                    recommended_relay_url,
+                    ..
+                }) if !recipients.iter().any(|(pk, _)| *pk == pubkey) => {
+                    recipients.push((pubkey, recommended_relay_url));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(BadgeAward {
+            badge: badge.ok_or(Error::TagMismatch)?,
+            recipients,
+        })
+    }
+
+    /// Create the kind-8 BadgeAward PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![ParsedTag::Address {
+            address: self.badge.clone(),
+            marker: None,
+        }
+        .into_tag()];
+        for (recipient, relay) in self.recipients.iter() {
+            tags.push(
+                ParsedTag::Pubkey {
+                    pubkey: *recipient,
+                    recommended_relay_url: relay.clone(),
+                    petname: None,
+                }
+                .into_tag(),
+            );
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::BadgeAward,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+}
+
+/// A badge someone displays on their profile
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProfileBadge {
+    /// The badge definition
+    pub badge: NAddr,
+
+    /// The award event
+    pub award: Id,
+
+    /// A relay where the award event may be found
+    pub award_relay: Option<UncheckedUrl>,
+}
+
+/// The badges someone displays on their profile, from a kind-30008 ProfileBadges
+/// event, in order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileBadges(pub Vec<ProfileBadge>);
+
+impl ProfileBadges {
+    /// Parse a kind-30008 ProfileBadges event.
+    ///
+    /// Badges are consecutive pairs of 'a' and 'e' tags. An 'a' tag not followed by
+    /// an 'e' tag, or an 'e' tag not preceded by an 'a' tag, is skipped.
+    pub fn from_event(event: &Event) -> Result<ProfileBadges, Error> {
+        if event.kind != EventKind::ProfileBadges {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut badges: Vec<ProfileBadge> = Vec::new();
+        let mut pending: Option<NAddr> = None;
+        for tag in event.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Address { address, .. })
+                    if address.kind == EventKind::BadgeDefinition =>
+                {
+                    pending = Some(address);
+                }
+                Ok(ParsedTag::Event {
+                    id,
+                    recommended_relay_url,
+                    ..
+                }) => {
+                    if let Some(badge) = pending.take() {
+                        badges.push(ProfileBadge {
+                            badge,
+                            award: id,
+                            award_relay: recommended_relay_url,
+                        });
+                    }
+                }
+                _ => pending = None,
+            }
+        }
+        Ok(ProfileBadges(badges))
+    }
+
+    /// Create the kind-30008 ProfileBadges PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", "profile_badges"])];
+        for badge in self.0.iter() {
+            tags.push(
+                ParsedTag::Address {
+                    address: badge.badge.clone(),
+                    marker: None,
+                }
+                .into_tag(),
+            );
+            tags.push(
+                ParsedTag::Event {
+                    id: badge.award,
+                    recommended_relay_url: badge.award_relay.clone(),
+                    marker: None,
+                    author_pubkey: None,
+                }
+                .into_tag(),
+            );
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ProfileBadges,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+
+    /// Whether a displayed badge is backed by one of `awards`: a validly signed
+    /// award with the badge's id, from the badge definition's author, of that
+    /// badge, to `owner` (the author of the ProfileBadges event)
+    pub fn is_backed(badge: &ProfileBadge, owner: PublicKey, awards: &[Event]) -> bool {
+        awards.iter().any(|event| {
+            event.id == badge.award
+                && event.pubkey == badge.badge.author
+                && event.verify(None).is_ok()
+                && match BadgeAward::from_event(event) {
+                    Ok(award) => award.badge == badge.badge && award.is_recipient(&owner),
+                    Err(_) => false,
+                }
+        })
+    }
+
+    /// The displayed badges that are backed by one of `awards`, in order. See
+    /// `is_backed()`.
+    pub fn validated(&self, owner: PublicKey, awards: &[Event]) -> Vec<&ProfileBadge> {
+        self.0
+            .iter()
+            .filter(|badge| ProfileBadges::is_backed(badge, owner, awards))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_badge_definition() {
+        let private_key = PrivateKey::generate();
+        let mut definition = BadgeDefinition::new("bravery".to_owned());
+        definition.name = Some("Medal of Bravery".to_owned());
+        definition.image = Some(BadgeImage {
+            url: UncheckedUrl("https://example.com/bravery.png".to_owned()),
+            dimensions: Some((1024, 1024)),
+        });
+        definition.thumbs.push(BadgeImage {
+            url: UncheckedUrl("https://example.com/bravery_32.png".to_owned()),
+            dimensions: Some((32, 32)),
+        });
+        let event = private_key
+            .sign_event(definition.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(BadgeDefinition::from_event(&event).unwrap(), definition);
+    }
+
+    #[test]
+    fn test_profile_badges_validation() {
+        let issuer = PrivateKey::generate();
+        let owner = PrivateKey::generate();
+        let imposter = PrivateKey::generate();
+        let badge = BadgeDefinition::new("bravery".to_owned()).address(issuer.public_key());
+
+        let mut award = BadgeAward::new(badge.clone());
+        assert!(award.add_recipient(PublicKey::mock(), None));
+        assert!(award.add_recipient(owner.public_key(), None));
+        assert!(!award.add_recipient(owner.public_key(), None));
+        let award_event = issuer
+            .sign_event(award.to_pre_event(issuer.public_key()))
+            .unwrap();
+        assert_eq!(BadgeAward::from_event(&award_event).unwrap(), award);
+
+        // Someone else awarding the issuer's badge
+        let fake_event = imposter
+            .sign_event(award.to_pre_event(imposter.public_key()))
+            .unwrap();
+
+        let profile_badges = ProfileBadges(vec![
+            ProfileBadge {
+                badge: badge.clone(),
+                award: award_event.id,
+                award_relay: None,
+            },
+            ProfileBadge {
+                badge: badge.clone(),
+                award: fake_event.id,
+                award_relay: None,
+            },
+        ]);
+        let event = owner
+            .sign_event(profile_badges.to_pre_event(owner.public_key()))
+            .unwrap();
+        let parsed = ProfileBadges::from_event(&event).unwrap();
+        assert_eq!(parsed, profile_badges);
+
+        let awards = vec![award_event, fake_event];
+        let validated = parsed.validated(owner.public_key(), &awards);
+        assert_eq!(validated, vec![&profile_badges.0[0]]);
+        assert!(parsed.validated(imposter.public_key(), &awards).is_empty());
+    }
+}
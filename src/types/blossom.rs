@@ -0,0 +1,377 @@
+use crate::{
+    Error, Event, EventKind, FileMetadata, HttpAuth, ParsedTag, PreEvent, PublicKey, Tag,
+    UncheckedUrl, Unixtime, Url,
+};
+use serde::{Deserialize, Serialize};
+
+/// The action a Blossom authorization event permits (its 't' tag)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BlossomVerb {
+    /// Fetch a blob
+    Get,
+
+    /// Upload a blob
+    Upload,
+
+    /// List a user's blobs
+    List,
+
+    /// Delete a blob
+    Delete,
+
+    /// Upload a blob for media optimization
+    Media,
+}
+
+impl BlossomVerb {
+    /// The 't' tag value
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            BlossomVerb::Get => "get",
+            BlossomVerb::Upload => "upload",
+            BlossomVerb::List => "list",
+            BlossomVerb::Delete => "delete",
+            BlossomVerb::Media => "media",
+        }
+    }
+
+    /// Parse a 't' tag value
+    pub fn from_str_opt(s: &str) -> Option<BlossomVerb> {
+        match s {
+            "get" => Some(BlossomVerb::Get),
+            "upload" => Some(BlossomVerb::Upload),
+            "list" => Some(BlossomVerb::List),
+            "delete" => Some(BlossomVerb::Delete),
+            "media" => Some(BlossomVerb::Media),
+            _ => None,
+        }
+    }
+}
+
+/// A Blossom authorization (kind 24242)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlossomAuth {
+    /// The permitted action
+    pub verb: BlossomVerb,
+
+    /// A human readable description of the action
+    pub content: String,
+
+    /// The sha256 hashes (hex) of the blobs this applies to. Upload, delete and media
+    /// authorizations must name the blob; for get and list, empty means any blob.
+    pub hashes: Vec<String>,
+
+    /// When the authorization expires
+    pub expiration: Unixtime,
+
+    /// The server domains this applies to. Empty means any server.
+    pub servers: Vec<String>,
+}
+
+impl BlossomAuth {
+    /// Create a new authorization for `verb`, expiring `seconds` from now
+    pub fn new(verb: BlossomVerb, content: String, seconds: i64) -> BlossomAuth {
+        BlossomAuth {
+            verb,
+            content,
+            hashes: vec![],
+            expiration: Unixtime(Unixtime::now().0 + seconds),
+            servers: vec![],
+        }
+    }
+
+    /// Limit the authorization to a blob
+    pub fn add_hash(&mut self, sha256: &str) {
+        self.hashes.push(sha256.to_lowercase());
+    }
+
+    /// Limit the authorization to a server, given by URL or by domain
+    pub fn add_server(&mut self, server: &str) {
+        self.servers.push(server_domain(server));
+    }
+
+    /// Create the authorization PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![
+            Tag::new(&["t", self.verb.as_str()]),
+            ParsedTag::Expiration(self.expiration).into_tag(),
+        ];
+        for hash in self.hashes.iter() {
+            tags.push(Tag::new(&["x", hash]));
+        }
+        for server in self.servers.iter() {
+            tags.push(Tag::new(&["server", server]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::Blossom,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+
+    /// Parse an authorization event. This does not verify it, see `verify()`.
+    pub fn from_event(event: &Event) -> Result<BlossomAuth, Error> {
+        if event.kind != EventKind::Blossom {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut verb: Option<BlossomVerb> = None;
+        let mut hashes: Vec<String> = Vec::new();
+        let mut servers: Vec<String> = Vec::new();
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "t" if verb.is_none() => verb = BlossomVerb::from_str_opt(tag.value()),
+                "x" => hashes.push(tag.value().to_lowercase()),
+                "server" => servers.push(server_domain(tag.value())),
+                _ => {}
+            }
+        }
+
+        Ok(BlossomAuth {
+            verb: verb.ok_or_else(|| Error::Blossom("Missing or unknown 't' tag".to_owned()))?,
+            content: event.content.clone(),
+            hashes,
+            expiration: event
+                .expiration()
+                .ok_or_else(|| Error::Blossom("Missing or invalid 'expiration' tag".to_owned()))?,
+            servers,
+        })
+    }
+
+    /// Verify an authorization event as the server `server` (a URL or domain) would,
+    /// for the action `verb` on the blob `sha256` (if the action is on a blob).
+    ///
+    /// Returns the public key of the authorized user.
+    pub fn verify(
+        event: &Event,
+        verb: BlossomVerb,
+        sha256: Option<&str>,
+        server: &str,
+    ) -> Result<PublicKey, Error> {
+        let now = Unixtime::now();
+        event.verify(Some(now))?;
+        let auth = BlossomAuth::from_event(event)?;
+
+        if auth.verb != verb {
+            return Err(Error::Blossom(format!(
+                "Authorization is for {}, not {}",
+                auth.verb.as_str(),
+                verb.as_str()
+            )));
+        }
+        if auth.expiration <= now {
+            return Err(Error::Blossom("Authorization has expired".to_owned()));
+        }
+        if let Some(hash) = sha256 {
+            let needs_hash = matches!(
+                verb,
+                BlossomVerb::Upload | BlossomVerb::Delete | BlossomVerb::Media
+            );
+            if needs_hash && auth.hashes.is_empty() {
+                return Err(Error::Blossom(
+                    "Authorization does not name a blob".to_owned(),
+                ));
+            }
+            if !auth.hashes.is_empty() && !auth.hashes.contains(&hash.to_lowercase()) {
+                return Err(Error::Blossom(
+                    "Authorization is not for this blob".to_owned(),
+                ));
+            }
+        }
+        if !auth.servers.is_empty() && !auth.servers.contains(&server_domain(server)) {
+            return Err(Error::Blossom(
+                "Authorization is not for this server".to_owned(),
+            ));
+        }
+
+        Ok(event.pubkey)
+    }
+
+    /// The value of an HTTP `Authorization` header carrying an authorization event
+    pub fn authorization_header(event: &Event) -> Result<String, Error> {
+        HttpAuth::authorization_header(event)
+    }
+}
+
+// The lowercase domain of a server given either as a URL or as a bare domain
+fn server_domain(server: &str) -> String {
+    let server = server.trim();
+    if let Ok(url) = url::Url::parse(server) {
+        if let Some(host) = url.host_str() {
+            return host.to_lowercase();
+        }
+    }
+    server.trim_end_matches('/').to_lowercase()
+}
+
+/// A Blossom blob descriptor, as returned by servers
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlobDescriptor {
+    /// Where the blob can be fetched
+    pub url: UncheckedUrl,
+
+    /// The sha256 hash (hex) of the blob
+    pub sha256: String,
+
+    /// The size of the blob in bytes
+    pub size: u64,
+
+    /// The mime type of the blob
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+
+    /// When the blob was uploaded
+    pub uploaded: Unixtime,
+
+    /// NIP-94 tags describing the blob, if the server provides them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nip94: Option<Vec<Tag>>,
+}
+
+impl BlobDescriptor {
+    /// Convert into FileMetadata, including the NIP-94 tags if present
+    pub fn to_file_metadata(&self) -> FileMetadata {
+        let mut tags = vec![Tag::new(&["url", &self.url.0])];
+        if let Some(nip94) = &self.nip94 {
+            tags.extend(nip94.iter().cloned());
+        }
+        let mut fm = FileMetadata::from_nip94_tags(&tags)
+            .unwrap_or_else(|| FileMetadata::new(self.url.clone()));
+        fm.url = self.url.clone();
+        fm.x = Some(self.sha256.clone());
+        fm.size = Some(self.size);
+        if fm.m.is_none() {
+            fm.m = self.mime_type.clone();
+        }
+        fm
+    }
+}
+
+/// A user's Blossom servers, from a kind-10063 UserServerList event, most
+/// preferred first
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlossomServerList(pub Vec<Url>);
+
+impl BlossomServerList {
+    /// Parse a kind-10063 UserServerList event. Invalid URLs are skipped.
+    pub fn from_event(event: &Event) -> Result<BlossomServerList, Error> {
+        if event.kind != EventKind::UserServerList {
+            return Err(Error::WrongEventKind);
+        }
+        let mut servers: Vec<Url> = Vec::new();
+        for tag in event.tags.iter() {
+            if tag.tagname() == "server" {
+                if let Ok(url) = Url::try_from_str(tag.value()) {
+                    if !servers.contains(&url) {
+                        servers.push(url);
+                    }
+                }
+            }
+        }
+        Ok(BlossomServerList(servers))
+    }
+
+    /// Create a kind-10063 UserServerList PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::UserServerList,
+            tags: self
+                .0
+                .iter()
+                .map(|url| Tag::new(&["server", url.as_str()]))
+                .collect(),
+            content: "".to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_blossom_auth() {
+        let private_key = PrivateKey::generate();
+        let fm = FileMetadata::from_bytes(UncheckedUrl("".to_owned()), b"hello");
+        let hash = fm.x.unwrap();
+
+        let mut auth = BlossomAuth::new(BlossomVerb::Upload, "Upload hello".to_owned(), 60);
+        auth.add_hash(&hash);
+        auth.add_server("https://CDN.example.com/");
+        let event = private_key
+            .sign_event(auth.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(BlossomAuth::from_event(&event).unwrap(), auth);
+
+        let pubkey = BlossomAuth::verify(
+            &event,
+            BlossomVerb::Upload,
+            Some(&hash),
+            "https://cdn.example.com",
+        )
+        .unwrap();
+        assert_eq!(pubkey, private_key.public_key());
+
+        // Wrong verb, blob, server
+        assert!(
+            BlossomAuth::verify(&event, BlossomVerb::Delete, Some(&hash), "cdn.example.com")
+                .is_err()
+        );
+        assert!(
+            BlossomAuth::verify(&event, BlossomVerb::Upload, Some("00"), "cdn.example.com")
+                .is_err()
+        );
+        assert!(
+            BlossomAuth::verify(&event, BlossomVerb::Upload, Some(&hash), "other.com").is_err()
+        );
+
+        // Expired
+        let mut expired = auth.clone();
+        expired.expiration = Unixtime(Unixtime::now().0 - 1);
+        let event = private_key
+            .sign_event(expired.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert!(BlossomAuth::verify(&event, BlossomVerb::Upload, None, "cdn.example.com").is_err());
+
+        // A delete authorization must name the blob
+        let delete = BlossomAuth::new(BlossomVerb::Delete, "Delete".to_owned(), 60);
+        let event = private_key
+            .sign_event(delete.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert!(
+            BlossomAuth::verify(&event, BlossomVerb::Delete, Some(&hash), "cdn.example.com")
+                .is_err()
+        );
+
+        // A get authorization without 'x' tags is for any blob
+        let get = BlossomAuth::new(BlossomVerb::Get, "Get".to_owned(), 60);
+        let event = private_key
+            .sign_event(get.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert!(
+            BlossomAuth::verify(&event, BlossomVerb::Get, Some(&hash), "cdn.example.com").is_ok()
+        );
+    }
+
+    #[test]
+    fn test_blob_descriptor() {
+        let json = r#"{
+            "url": "https://cdn.example.com/b1674191a88ec5cdd733e4240a81803105dc412d6c6708d53ab94fc248f4f553.pdf",
+            "sha256": "b1674191a88ec5cdd733e4240a81803105dc412d6c6708d53ab94fc248f4f553",
+            "size": 184292,
+            "type": "application/pdf",
+            "uploaded": 1725105921
+        }"#;
+        let descriptor: BlobDescriptor = serde_json::from_str(json).unwrap();
+        assert_eq!(descriptor.size, 184292);
+        let fm = descriptor.to_file_metadata();
+        assert_eq!(fm.m.as_deref(), Some("application/pdf"));
+        assert_eq!(fm.x.as_deref(), Some(descriptor.sha256.as_str()));
+    }
+}
@@ -0,0 +1,733 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A calendar date, as used by date-based calendar events (YYYY-MM-DD)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CalendarDate {
+    /// The year
+    pub year: u16,
+
+    /// The month, 1 to 12
+    pub month: u8,
+
+    /// The day of the month, 1 to the length of the month
+    pub day: u8,
+}
+
+impl CalendarDate {
+    /// Parse a YYYY-MM-DD date
+    pub fn try_from_str(s: &str) -> Result<CalendarDate, Error> {
+        let err = || Error::Calendar(format!("Invalid date: {s}"));
+        let mut parts = s.trim().split('-');
+        let (year, month, day) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(y), Some(m), Some(d), None) if y.len() == 4 && m.len() == 2 && d.len() == 2 => (
+                y.parse::<u16>().map_err(|_| err())?,
+                m.parse::<u8>().map_err(|_| err())?,
+                d.parse::<u8>().map_err(|_| err())?,
+            ),
+            _ => return Err(err()),
+        };
+        if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+            return Err(err());
+        }
+        Ok(CalendarDate { year, month, day })
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+impl fmt::Display for CalendarDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+/// When a calendar event happens
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CalendarEventTime {
+    /// All-day, from a kind-31922 DateBasedCalendarEvent. The end date is exclusive.
+    Date {
+        /// The first day
+        start: CalendarDate,
+
+        /// The day after the last day
+        end: Option<CalendarDate>,
+    },
+
+    /// At a time, from a kind-31923 TimeBasedCalendarEvent
+    Time {
+        /// The start time
+        start: Unixtime,
+
+        /// The end time (exclusive)
+        end: Option<Unixtime>,
+
+        /// The IANA timezone of the start, e.g. "America/Costa_Rica"
+        start_tzid: Option<String>,
+
+        /// The IANA timezone of the end, if different from the start
+        end_tzid: Option<String>,
+    },
+}
+
+/// A person taking part in a calendar event
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalendarParticipant {
+    /// Who
+    pub pubkey: PublicKey,
+
+    /// A relay where they may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// Their role, e.g. "speaker"
+    pub role: Option<String>,
+}
+
+/// A NIP-52 calendar event (kind 31922 or 31923)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalendarEvent {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The title
+    pub title: String,
+
+    /// A short summary
+    pub summary: Option<String>,
+
+    /// An image
+    pub image: Option<UncheckedUrl>,
+
+    /// The description
+    pub content: String,
+
+    /// When it happens. This decides the event kind.
+    pub time: CalendarEventTime,
+
+    /// Locations, e.g. addresses or call links
+    pub locations: Vec<String>,
+
+    /// The geohash of the location
+    pub geohash: Option<String>,
+
+    /// The participants
+    pub participants: Vec<CalendarParticipant>,
+
+    /// Hashtags
+    pub hashtags: Vec<String>,
+
+    /// Reference links
+    pub references: Vec<UncheckedUrl>,
+
+    /// The calendars this event is requesting to be in
+    pub calendars: Vec<NAddr>,
+}
+
+impl CalendarEvent {
+    /// Create a new calendar event
+    pub fn new(d: String, title: String, time: CalendarEventTime) -> CalendarEvent {
+        CalendarEvent {
+            d,
+            title,
+            summary: None,
+            image: None,
+            content: "".to_owned(),
+            time,
+            locations: vec![],
+            geohash: None,
+            participants: vec![],
+            hashtags: vec![],
+            references: vec![],
+            calendars: vec![],
+        }
+    }
+
+    /// The event kind, from the kind of time
+    pub fn kind(&self) -> EventKind {
+        match self.time {
+            CalendarEventTime::Date { .. } => EventKind::DateBasedCalendarEvent,
+            CalendarEventTime::Time { .. } => EventKind::TimeBasedCalendarEvent,
+        }
+    }
+
+    /// The address of this calendar event, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: self.kind(),
+            author,
+        }
+    }
+
+    /// Parse a kind-31922 or kind-31923 calendar event
+    pub fn from_event(event: &Event) -> Result<CalendarEvent, Error> {
+        let date_based = match event.kind {
+            EventKind::DateBasedCalendarEvent => true,
+            EventKind::TimeBasedCalendarEvent => false,
+            _ => return Err(Error::WrongEventKind),
+        };
+
+        let mut d = String::new();
+        let mut title: Option<String> = None;
+        let mut summary: Option<String> = None;
+        let mut image: Option<UncheckedUrl> = None;
+        let mut start: Option<&str> = None;
+        let mut end: Option<&str> = None;
+        let mut start_tzid: Option<String> = None;
+        let mut end_tzid: Option<String> = None;
+        let mut locations: Vec<String> = Vec::new();
+        let mut geohash: Option<String> = None;
+        let mut participants: Vec<CalendarParticipant> = Vec::new();
+        let mut hashtags: Vec<String> = Vec::new();
+        let mut references: Vec<UncheckedUrl> = Vec::new();
+        let mut calendars: Vec<NAddr> = Vec::new();
+
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "title" | "name" if title.is_none() => title = Some(tag.value().to_owned()),
+                "summary" => summary = Some(tag.value().to_owned()),
+                "image" => image = Some(UncheckedUrl(tag.value().to_owned())),
+                "start" => start = Some(tag.value()),
+                "end" => end = Some(tag.value()),
+                "start_tzid" => start_tzid = Some(tag.value().to_owned()),
+                "end_tzid" => end_tzid = Some(tag.value().to_owned()),
+                "location" => locations.push(tag.value().to_owned()),
+                "g" => geohash = Some(tag.value().to_owned()),
+                "p" => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        participants.push(CalendarParticipant {
+                            pubkey,
+                            relay: tag.get_opt_index(2).map(|s| UncheckedUrl(s.to_owned())),
+                            role: tag.get_opt_index(3).map(|s| s.to_owned()),
+                        });
+                    }
+                }
+                "t" => hashtags.push(tag.value().to_owned()),
+                "r" => references.push(UncheckedUrl(tag.value().to_owned())),
+                "a" => {
+                    if let Ok(ParsedTag::Address { address, .. }) = tag.parse() {
+                        if address.kind == EventKind::Calendar {
+                            calendars.push(address);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let start = start.ok_or_else(|| Error::Calendar("Missing 'start' tag".to_owned()))?;
+        let time = if date_based {
+            CalendarEventTime::Date {
+                start: CalendarDate::try_from_str(start)?,
+                end: end.map(CalendarDate::try_from_str).transpose()?,
+            }
+        } else {
+            CalendarEventTime::Time {
+                start: Unixtime(start.parse::<i64>()?),
+                end: end.map(|e| e.parse::<i64>().map(Unixtime)).transpose()?,
+                start_tzid,
+                end_tzid,
+            }
+        };
+
+        Ok(CalendarEvent {
+            d,
+            title: title.unwrap_or_default(),
+            summary,
+            image,
+            content: event.content.clone(),
+            time,
+            locations,
+            geohash,
+            participants,
+            hashtags,
+            references,
+            calendars,
+        })
+    }
+
+    /// Create the kind-31922 or kind-31923 PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d]), Tag::new(&["title", &self.title])];
+        if let Some(summary) = &self.summary {
+            tags.push(Tag::new(&["summary", summary]));
+        }
+        if let Some(image) = &self.image {
+            tags.push(Tag::new(&["image", &image.0]));
+        }
+        match &self.time {
+            CalendarEventTime::Date { start, end } => {
+                tags.push(Tag::new(&["start", &start.to_string()]));
+                if let Some(end) = end {
+                    tags.push(Tag::new(&["end", &end.to_string()]));
+                }
+            }
+            CalendarEventTime::Time {
+                start,
+                end,
+                start_tzid,
+                end_tzid,
+            } => {
+                tags.push(Tag::new(&["start", &start.0.to_string()]));
+                if let Some(end) = end {
+                    tags.push(Tag::new(&["end", &end.0.to_string()]));
+                }
+                if let Some(tzid) = start_tzid {
+                    tags.push(Tag::new(&["start_tzid", tzid]));
+                }
+                if let Some(tzid) = end_tzid {
+                    tags.push(Tag::new(&["end_tzid", tzid]));
+                }
+            }
+        }
+        for location in self.locations.iter() {
+            tags.push(Tag::new(&["location", location]));
+        }
+        if let Some(geohash) = &self.geohash {
+            tags.push(Tag::new(&["g", geohash]));
+        }
+        for participant in self.participants.iter() {
+            let relay = participant.relay.as_ref().map(|r| r.0.as_str());
+            let mut tag = Tag::new(&["p", &participant.pubkey.as_hex_string()]);
+            if relay.is_some() || participant.role.is_some() {
+                tag.set_index(2, relay.unwrap_or("").to_owned());
+            }
+            if let Some(role) = &participant.role {
+                tag.set_index(3, role.clone());
+            }
+            tags.push(tag);
+        }
+        for hashtag in self.hashtags.iter() {
+            tags.push(Tag::new(&["t", hashtag]));
+        }
+        for reference in self.references.iter() {
+            tags.push(Tag::new(&["r", &reference.0]));
+        }
+        for calendar in self.calendars.iter() {
+            tags.push(
+                ParsedTag::Address {
+                    address: calendar.clone(),
+                    marker: None,
+                }
+                .into_tag(),
+            );
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.kind(),
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// A NIP-52 calendar (kind 31924), a collection of calendar events
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Calendar {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The title
+    pub title: String,
+
+    /// The description
+    pub content: String,
+
+    /// The calendar events in this calendar
+    pub events: Vec<NAddr>,
+}
+
+impl Calendar {
+    /// Parse a kind-31924 Calendar event. Members that are not calendar events are
+    /// skipped.
+    pub fn from_event(event: &Event) -> Result<Calendar, Error> {
+        if event.kind != EventKind::Calendar {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut calendar = Calendar {
+            content: event.content.clone(),
+            ..Default::default()
+        };
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => calendar.d = tag.value().to_owned(),
+                "title" => calendar.title = tag.value().to_owned(),
+                "a" => {
+                    if let Ok(ParsedTag::Address { address, .. }) = tag.parse() {
+                        if is_calendar_event_kind(address.kind) {
+                            calendar.events.push(address);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(calendar)
+    }
+
+    /// Whether a calendar event is in this calendar
+    pub fn contains(&self, address: &NAddr) -> bool {
+        self.events.contains(address)
+    }
+
+    /// Create the kind-31924 Calendar PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d]), Tag::new(&["title", &self.title])];
+        for address in self.events.iter() {
+            tags.push(
+                ParsedTag::Address {
+                    address: address.clone(),
+                    marker: None,
+                }
+                .into_tag(),
+            );
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::Calendar,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// The response in a calendar event RSVP
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RsvpStatus {
+    /// Will attend
+    Accepted,
+
+    /// Will not attend
+    Declined,
+
+    /// Might attend
+    Tentative,
+}
+
+impl RsvpStatus {
+    /// The 'status' tag value
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            RsvpStatus::Accepted => "accepted",
+            RsvpStatus::Declined => "declined",
+            RsvpStatus::Tentative => "tentative",
+        }
+    }
+
+    /// Parse a 'status' tag value
+    pub fn from_str_opt(s: &str) -> Option<RsvpStatus> {
+        match s {
+            "accepted" => Some(RsvpStatus::Accepted),
+            "declined" => Some(RsvpStatus::Declined),
+            "tentative" => Some(RsvpStatus::Tentative),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an RSVPing person would be free or busy during the calendar event
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FreeBusy {
+    /// Free
+    Free,
+
+    /// Busy
+    Busy,
+}
+
+impl FreeBusy {
+    /// The 'fb' tag value
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            FreeBusy::Free => "free",
+            FreeBusy::Busy => "busy",
+        }
+    }
+
+    /// Parse an 'fb' tag value
+    pub fn from_str_opt(s: &str) -> Option<FreeBusy> {
+        match s {
+            "free" => Some(FreeBusy::Free),
+            "busy" => Some(FreeBusy::Busy),
+            _ => None,
+        }
+    }
+}
+
+/// A NIP-52 calendar event RSVP (kind 31925)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CalendarEventRsvp {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The calendar event being responded to
+    pub event: NAddr,
+
+    /// The specific revision of the calendar event, if known
+    pub event_id: Option<Id>,
+
+    /// The response
+    pub status: RsvpStatus,
+
+    /// Free or busy. Not used when declining.
+    pub free_busy: Option<FreeBusy>,
+
+    /// A note
+    pub content: String,
+}
+
+impl CalendarEventRsvp {
+    /// Create a new RSVP to a calendar event
+    pub fn new(event: NAddr, status: RsvpStatus) -> CalendarEventRsvp {
+        CalendarEventRsvp {
+            d: format!("{}:{}", event.author.as_hex_string(), event.d),
+            event,
+            event_id: None,
+            status,
+            free_busy: None,
+            content: "".to_owned(),
+        }
+    }
+
+    /// Parse a kind-31925 CalendarEventRsvp event
+    pub fn from_event(event: &Event) -> Result<CalendarEventRsvp, Error> {
+        if event.kind != EventKind::CalendarEventRsvp {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut d = String::new();
+        let mut address: Option<NAddr> = None;
+        let mut event_id: Option<Id> = None;
+        let mut status: Option<RsvpStatus> = None;
+        let mut free_busy: Option<FreeBusy> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => d = tag.value().to_owned(),
+                "a" if address.is_none() => {
+                    if let Ok(ParsedTag::Address { address: a, .. }) = tag.parse() {
+                        address = Some(a);
+                    }
+                }
+                "e" if event_id.is_none() => event_id = Id::try_from_hex_string(tag.value()).ok(),
+                "status" => status = RsvpStatus::from_str_opt(tag.value()),
+                "fb" => free_busy = FreeBusy::from_str_opt(tag.value()),
+                _ => {}
+            }
+        }
+
+        let status =
+            status.ok_or_else(|| Error::Calendar("Missing or unknown 'status' tag".to_owned()))?;
+        if status == RsvpStatus::Declined {
+            free_busy = None;
+        }
+
+        Ok(CalendarEventRsvp {
+            d,
+            event: address.ok_or_else(|| Error::Calendar("Missing 'a' tag".to_owned()))?,
+            event_id,
+            status,
+            free_busy,
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the kind-31925 CalendarEventRsvp PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![
+            Tag::new(&["d", &self.d]),
+            ParsedTag::Address {
+                address: self.event.clone(),
+                marker: None,
+            }
+            .into_tag(),
+        ];
+        if let Some(id) = self.event_id {
+            tags.push(Tag::new(&["e", &id.as_hex_string()]));
+        }
+        tags.push(Tag::new(&["status", self.status.as_str()]));
+        if let Some(fb) = self.free_busy {
+            if self.status != RsvpStatus::Declined {
+                tags.push(Tag::new(&["fb", fb.as_str()]));
+            }
+        }
+        tags.push(Tag::new(&["p", &self.event.author.as_hex_string()]));
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::CalendarEventRsvp,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+
+    /// The RSVPs to the calendar event at `address`, from a set of events, as
+    /// (responder, rsvp). Only each person's latest RSVP is used.
+    pub fn for_event(events: &[Event], address: &NAddr) -> Vec<(PublicKey, CalendarEventRsvp)> {
+        let mut latest: HashMap<PublicKey, (Unixtime, CalendarEventRsvp)> = HashMap::new();
+        for event in events.iter() {
+            let rsvp = match CalendarEventRsvp::from_event(event) {
+                Ok(r) => r,
+                Err(_) => continue,
+            };
+            if rsvp.event != *address {
+                continue;
+            }
+            match latest.get(&event.pubkey) {
+                Some((at, _)) if *at >= event.created_at => {}
+                _ => {
+                    let _ = latest.insert(event.pubkey, (event.created_at, rsvp));
+                }
+            }
+        }
+
+        let mut output: Vec<(PublicKey, CalendarEventRsvp)> = latest
+            .into_iter()
+            .map(|(pk, (_, rsvp))| (pk, rsvp))
+            .collect();
+        output.sort_by_key(|(pk, _)| *pk);
+        output
+    }
+}
+
+fn is_calendar_event_kind(kind: EventKind) -> bool {
+    kind == EventKind::DateBasedCalendarEvent || kind == EventKind::TimeBasedCalendarEvent
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_calendar_date() {
+        let date = CalendarDate::try_from_str("2024-02-29").unwrap();
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert!(CalendarDate::try_from_str("2000-02-29").is_ok());
+        for invalid in [
+            "2024-02-30",
+            "2024-02-31",
+            "2023-02-29",
+            "1900-02-29",
+            "2023-04-31",
+            "2023-13-01",
+            "2023-01-00",
+            "2023-1-01",
+        ] {
+            assert!(CalendarDate::try_from_str(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_calendar_event() {
+        let private_key = PrivateKey::generate();
+        let calendar = NAddr {
+            d: "work".to_owned(),
+            relays: vec![],
+            kind: EventKind::Calendar,
+            author: private_key.public_key(),
+        };
+
+        let mut event = CalendarEvent::new(
+            "meetup".to_owned(),
+            "Nostr Meetup".to_owned(),
+            CalendarEventTime::Time {
+                start: Unixtime(1_700_000_000),
+                end: Some(Unixtime(1_700_007_200)),
+                start_tzid: Some("America/Costa_Rica".to_owned()),
+                end_tzid: None,
+            },
+        );
+        event.locations.push("Somewhere".to_owned());
+        event.participants.push(CalendarParticipant {
+            pubkey: PublicKey::mock(),
+            relay: None,
+            role: Some("speaker".to_owned()),
+        });
+        event.calendars.push(calendar);
+        let signed = private_key
+            .sign_event(event.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(signed.kind, EventKind::TimeBasedCalendarEvent);
+        assert_eq!(CalendarEvent::from_event(&signed).unwrap(), event);
+
+        let mut all_day = CalendarEvent::new(
+            "holiday".to_owned(),
+            "Holiday".to_owned(),
+            CalendarEventTime::Date {
+                start: CalendarDate::try_from_str("2024-12-25").unwrap(),
+                end: None,
+            },
+        );
+        all_day.hashtags.push("holiday".to_owned());
+        let signed = private_key
+            .sign_event(all_day.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(signed.kind, EventKind::DateBasedCalendarEvent);
+        assert_eq!(CalendarEvent::from_event(&signed).unwrap(), all_day);
+
+        assert!(CalendarDate::try_from_str("2024-13-01").is_err());
+        assert!(CalendarDate::try_from_str("24-12-01").is_err());
+    }
+
+    #[test]
+    fn test_calendar_event_rsvps() {
+        let organizer = PublicKey::mock();
+        let address = NAddr {
+            d: "meetup".to_owned(),
+            relays: vec![],
+            kind: EventKind::TimeBasedCalendarEvent,
+            author: organizer,
+        };
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let rsvp = |key: &PrivateKey, status: RsvpStatus, at: i64| {
+            let mut rsvp = CalendarEventRsvp::new(address.clone(), status);
+            rsvp.free_busy = Some(FreeBusy::Busy);
+            let mut pre_event = rsvp.to_pre_event(key.public_key());
+            pre_event.created_at = Unixtime(at);
+            key.sign_event(pre_event).unwrap()
+        };
+
+        let events = vec![
+            rsvp(&alice, RsvpStatus::Tentative, 100),
+            rsvp(&alice, RsvpStatus::Accepted, 200),
+            rsvp(&bob, RsvpStatus::Declined, 150),
+        ];
+        let rsvps = CalendarEventRsvp::for_event(&events, &address);
+        assert_eq!(rsvps.len(), 2);
+        let alices = rsvps
+            .iter()
+            .find(|(pk, _)| *pk == alice.public_key())
+            .unwrap();
+        assert_eq!(alices.1.status, RsvpStatus::Accepted);
+        assert_eq!(alices.1.free_busy, Some(FreeBusy::Busy));
+        let bobs = rsvps
+            .iter()
+            .find(|(pk, _)| *pk == bob.public_key())
+            .unwrap();
+        assert_eq!(bobs.1.free_busy, None);
+
+        let mut other = address.clone();
+        other.d = "other".to_owned();
+        assert!(CalendarEventRsvp::for_event(&events, &other).is_empty());
+    }
+}
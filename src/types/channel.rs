@@ -0,0 +1,517 @@
+use crate::types::{
+    Event, EventKind, Id, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// NIP-28 channel information, the JSON content of ChannelCreation (kind 40) and
+/// ChannelMetadata (kind 41) events
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelInfo {
+    /// The channel name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// A description of the channel
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+
+    /// The channel picture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+
+    /// Relays where the channel's events may be found
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relays: Vec<UncheckedUrl>,
+}
+
+impl ChannelInfo {
+    /// Create the kind-40 ChannelCreation PreEvent
+    pub fn to_creation_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ChannelCreation,
+            tags: vec![],
+            content: serde_json::to_string(self)?,
+        })
+    }
+}
+
+/// A NIP-28 public chat channel
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Channel {
+    /// The id of the ChannelCreation event
+    pub id: Id,
+
+    /// Who created the channel. Only they may update its metadata.
+    pub creator: PublicKey,
+
+    /// The current channel information
+    pub info: ChannelInfo,
+
+    /// Categories, from the latest metadata event
+    pub categories: Vec<String>,
+
+    /// When the current information was published
+    pub updated_at: Unixtime,
+}
+
+impl Channel {
+    /// Parse a kind-40 ChannelCreation event
+    pub fn from_creation_event(event: &Event) -> Result<Channel, Error> {
+        if event.kind != EventKind::ChannelCreation {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(Channel {
+            id: event.id,
+            creator: event.pubkey,
+            info: serde_json::from_str(&event.content)?,
+            categories: vec![],
+            updated_at: event.created_at,
+        })
+    }
+
+    /// Apply a kind-41 ChannelMetadata event. Returns false, changing nothing, if it
+    /// is not for this channel, is not from the creator, or is not newer than the
+    /// current information.
+    pub fn apply_metadata(&mut self, event: &Event) -> bool {
+        if event.kind != EventKind::ChannelMetadata
+            || event.pubkey != self.creator
+            || event.created_at <= self.updated_at
+            || channel_reference(event) != Some(self.id)
+        {
+            return false;
+        }
+        let Ok(info) = serde_json::from_str(&event.content) else {
+            return false;
+        };
+        self.info = info;
+        self.categories = event
+            .tags
+            .iter()
+            .filter(|t| t.tagname() == "t")
+            .map(|t| t.value().to_owned())
+            .collect();
+        self.updated_at = event.created_at;
+        true
+    }
+
+    /// Create the kind-41 ChannelMetadata PreEvent with the current information
+    pub fn to_metadata_pre_event(&self) -> Result<PreEvent, Error> {
+        let mut tags = vec![root_tag(self.id, self.info.relays.first().cloned())];
+        for category in self.categories.iter() {
+            tags.push(Tag::new(&["t", category]));
+        }
+
+        Ok(PreEvent {
+            pubkey: self.creator,
+            created_at: Unixtime::now(),
+            kind: EventKind::ChannelMetadata,
+            tags,
+            content: serde_json::to_string(&self.info)?,
+        })
+    }
+}
+
+/// A NIP-28 channel message (kind 42)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelMessage {
+    /// The channel (the ChannelCreation event id)
+    pub channel: Id,
+
+    /// A relay where the channel may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// The message being replied to
+    pub reply_to: Option<Id>,
+
+    /// People being replied to
+    pub pubkeys: Vec<PublicKey>,
+
+    /// The message
+    pub content: String,
+}
+
+impl ChannelMessage {
+    /// Create a new top-level message in a channel
+    pub fn new(channel: Id, relay: Option<UncheckedUrl>, content: String) -> ChannelMessage {
+        ChannelMessage {
+            channel,
+            relay,
+            reply_to: None,
+            pubkeys: vec![],
+            content,
+        }
+    }
+
+    /// Create a reply to a message in a channel
+    pub fn reply(
+        channel: Id,
+        relay: Option<UncheckedUrl>,
+        parent: &Event,
+        content: String,
+    ) -> ChannelMessage {
+        ChannelMessage {
+            channel,
+            relay,
+            reply_to: Some(parent.id),
+            pubkeys: vec![parent.pubkey],
+            content,
+        }
+    }
+
+    /// Parse a kind-42 ChannelMessage event.
+    ///
+    /// The channel is the 'e' tag marked "root", or else the first 'e' tag. The
+    /// message replied to is the 'e' tag marked "reply".
+    pub fn from_event(event: &Event) -> Result<ChannelMessage, Error> {
+        if event.kind != EventKind::ChannelMessage {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut root: Option<(Id, Option<UncheckedUrl>)> = None;
+        let mut first: Option<(Id, Option<UncheckedUrl>)> = None;
+        let mut reply_to: Option<Id> = None;
+        let mut pubkeys: Vec<PublicKey> = Vec::new();
+        for tag in event.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Event {
+                    id,
+                    recommended_relay_url,
+                    marker,
+                    ..
+                }) => match marker.as_deref() {
+                    Some("root") if root.is_none() => root = Some((id, recommended_relay_url)),
+                    Some("reply") if reply_to.is_none() => reply_to = Some(id),
+                    _ if first.is_none() => first = Some((id, recommended_relay_url)),
+                    _ => {}
+                },
+                Ok(ParsedTag::Pubkey { pubkey, .. }) => pubkeys.push(pubkey),
+                _ => {}
+            }
+        }
+
+        let (channel, relay) = root.or(first).ok_or(Error::TagMismatch)?;
+        Ok(ChannelMessage {
+            channel,
+            relay,
+            reply_to,
+            pubkeys,
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the kind-42 ChannelMessage PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![root_tag(self.channel, self.relay.clone())];
+        if let Some(reply_to) = self.reply_to {
+            tags.push(
+                ParsedTag::Event {
+                    id: reply_to,
+                    recommended_relay_url: self.relay.clone(),
+                    marker: Some("reply".to_owned()),
+                    author_pubkey: None,
+                }
+                .into_tag(),
+            );
+        }
+        for pubkey in self.pubkeys.iter() {
+            tags.push(
+                ParsedTag::Pubkey {
+                    pubkey: *pubkey,
+                    recommended_relay_url: self.relay.clone(),
+                    petname: None,
+                }
+                .into_tag(),
+            );
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ChannelMessage,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// A NIP-28 request to hide a channel message (kind 43). It applies only to what
+/// its author sees.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelHide {
+    /// The message to hide
+    pub message: Id,
+
+    /// Why
+    pub reason: Option<String>,
+}
+
+impl ChannelHide {
+    /// Parse a kind-43 ChannelHideMessage event
+    pub fn from_event(event: &Event) -> Result<ChannelHide, Error> {
+        if event.kind != EventKind::ChannelHideMessage {
+            return Err(Error::WrongEventKind);
+        }
+        let message = event
+            .tags
+            .iter()
+            .find_map(|tag| match tag.parse() {
+                Ok(ParsedTag::Event { id, .. }) => Some(id),
+                _ => None,
+            })
+            .ok_or(Error::TagMismatch)?;
+        Ok(ChannelHide {
+            message,
+            reason: read_reason(&event.content),
+        })
+    }
+
+    /// Create the kind-43 ChannelHideMessage PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ChannelHideMessage,
+            tags: vec![Tag::new(&["e", &self.message.as_hex_string()])],
+            content: write_reason(&self.reason)?,
+        })
+    }
+}
+
+/// A NIP-28 request to mute a user's channel messages (kind 44). It applies only to
+/// what its author sees.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChannelMute {
+    /// The user to mute
+    pub pubkey: PublicKey,
+
+    /// Why
+    pub reason: Option<String>,
+}
+
+impl ChannelMute {
+    /// Parse a kind-44 ChannelMuteUser event
+    pub fn from_event(event: &Event) -> Result<ChannelMute, Error> {
+        if event.kind != EventKind::ChannelMuteUser {
+            return Err(Error::WrongEventKind);
+        }
+        let pubkey = event
+            .tags
+            .iter()
+            .find_map(|tag| match tag.parse() {
+                Ok(ParsedTag::Pubkey { pubkey, .. }) => Some(pubkey),
+                _ => None,
+            })
+            .ok_or(Error::TagMismatch)?;
+        Ok(ChannelMute {
+            pubkey,
+            reason: read_reason(&event.content),
+        })
+    }
+
+    /// Create the kind-44 ChannelMuteUser PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ChannelMuteUser,
+            tags: vec![Tag::new(&["p", &self.pubkey.as_hex_string()])],
+            content: write_reason(&self.reason)?,
+        })
+    }
+}
+
+/// The state of a NIP-28 channel, reduced from its events
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ChannelState {
+    /// The channel, if its creation event was seen
+    pub channel: Option<Channel>,
+
+    /// The messages each user has hidden
+    pub hidden: BTreeMap<PublicKey, BTreeSet<Id>>,
+
+    /// The users each user has muted
+    pub muted: BTreeMap<PublicKey, BTreeSet<PublicKey>>,
+}
+
+impl ChannelState {
+    /// Reduce the events of the channel created by event `channel_id`: its creation
+    /// event, metadata updates from the creator, and hide and mute requests. Other
+    /// events are skipped.
+    ///
+    /// Signatures are not checked here.
+    pub fn reduce(channel_id: Id, events: &[Event]) -> ChannelState {
+        let mut state = ChannelState {
+            channel: events
+                .iter()
+                .find(|e| e.id == channel_id)
+                .and_then(|e| Channel::from_creation_event(e).ok()),
+            ..Default::default()
+        };
+
+        for event in events.iter() {
+            match event.kind {
+                EventKind::ChannelMetadata => {
+                    if let Some(channel) = state.channel.as_mut() {
+                        let _ = channel.apply_metadata(event);
+                    }
+                }
+                EventKind::ChannelHideMessage => {
+                    if let Ok(hide) = ChannelHide::from_event(event) {
+                        let _ = state
+                            .hidden
+                            .entry(event.pubkey)
+                            .or_default()
+                            .insert(hide.message);
+                    }
+                }
+                EventKind::ChannelMuteUser => {
+                    if let Ok(mute) = ChannelMute::from_event(event) {
+                        let _ = state
+                            .muted
+                            .entry(event.pubkey)
+                            .or_default()
+                            .insert(mute.pubkey);
+                    }
+                }
+                _ => {}
+            }
+        }
+        state
+    }
+
+    /// Whether `viewer` has hidden this message, or muted its author
+    pub fn is_hidden_for(&self, viewer: &PublicKey, message: &Event) -> bool {
+        self.hidden
+            .get(viewer)
+            .is_some_and(|ids| ids.contains(&message.id))
+            || self
+                .muted
+                .get(viewer)
+                .is_some_and(|pubkeys| pubkeys.contains(&message.pubkey))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Reason {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+fn read_reason(content: &str) -> Option<String> {
+    serde_json::from_str::<Reason>(content)
+        .ok()
+        .and_then(|r| r.reason)
+}
+
+fn write_reason(reason: &Option<String>) -> Result<String, Error> {
+    Ok(serde_json::to_string(&Reason {
+        reason: reason.clone(),
+    })?)
+}
+
+// The channel a metadata event refers to: the 'e' tag marked "root", or else the
+// first 'e' tag
+fn channel_reference(event: &Event) -> Option<Id> {
+    let mut first: Option<Id> = None;
+    for tag in event.tags.iter() {
+        if let Ok(ParsedTag::Event { id, marker, .. }) = tag.parse() {
+            if marker.as_deref() == Some("root") {
+                return Some(id);
+            }
+            first = first.or(Some(id));
+        }
+    }
+    first
+}
+
+fn root_tag(channel: Id, relay: Option<UncheckedUrl>) -> Tag {
+    ParsedTag::Event {
+        id: channel,
+        recommended_relay_url: relay,
+        marker: Some("root".to_owned()),
+        author_pubkey: None,
+    }
+    .into_tag()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_channel_message_round_trip() {
+        let private_key = PrivateKey::generate();
+        let relay = Some(UncheckedUrl("wss://relay.example.com".to_owned()));
+        let root = ChannelMessage::new(Id::mock(), relay.clone(), "hello".to_owned());
+        let root_event = private_key
+            .sign_event(root.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(ChannelMessage::from_event(&root_event).unwrap(), root);
+
+        let reply = ChannelMessage::reply(Id::mock(), relay, &root_event, "hi".to_owned());
+        let reply_event = private_key
+            .sign_event(reply.to_pre_event(private_key.public_key()))
+            .unwrap();
+        let parsed = ChannelMessage::from_event(&reply_event).unwrap();
+        assert_eq!(parsed, reply);
+        assert_eq!(parsed.reply_to, Some(root_event.id));
+        assert_eq!(parsed.pubkeys, vec![private_key.public_key()]);
+    }
+
+    #[test]
+    fn test_channel_state() {
+        let creator = PrivateKey::generate();
+        let other = PrivateKey::generate();
+        let viewer = PrivateKey::generate();
+
+        let info = ChannelInfo {
+            name: Some("Pizza".to_owned()),
+            ..Default::default()
+        };
+        let mut pre_event = info.to_creation_pre_event(creator.public_key()).unwrap();
+        pre_event.created_at = Unixtime(1000);
+        let creation = creator.sign_event(pre_event).unwrap();
+
+        let mut channel = Channel::from_creation_event(&creation).unwrap();
+        channel.info.about = Some("All about pizza".to_owned());
+        channel.categories = vec!["food".to_owned()];
+        let mut pre_event = channel.to_metadata_pre_event().unwrap();
+        pre_event.created_at = Unixtime(2000);
+        let update = creator.sign_event(pre_event.clone()).unwrap();
+
+        // The same update from someone else is ignored
+        pre_event.pubkey = other.public_key();
+        pre_event.created_at = Unixtime(3000);
+        let hijack = other.sign_event(pre_event).unwrap();
+
+        let message = ChannelMessage::new(creation.id, None, "spam".to_owned());
+        let message = other
+            .sign_event(message.to_pre_event(other.public_key()))
+            .unwrap();
+        let mute = ChannelMute {
+            pubkey: other.public_key(),
+            reason: Some("spam".to_owned()),
+        };
+        let mute = viewer
+            .sign_event(mute.to_pre_event(viewer.public_key()).unwrap())
+            .unwrap();
+        assert_eq!(
+            ChannelMute::from_event(&mute).unwrap().reason.as_deref(),
+            Some("spam")
+        );
+
+        let state = ChannelState::reduce(creation.id, &[hijack, creation, mute, update]);
+        let current = state.channel.as_ref().unwrap();
+        assert_eq!(current.info.name.as_deref(), Some("Pizza"));
+        assert_eq!(current.info.about.as_deref(), Some("All about pizza"));
+        assert_eq!(current.categories, vec!["food".to_owned()]);
+        assert_eq!(current.updated_at, Unixtime(2000));
+        assert!(state.is_hidden_for(&viewer.public_key(), &message));
+        assert!(!state.is_hidden_for(&creator.public_key(), &message));
+    }
+}
@@ -0,0 +1,227 @@
+use crate::types::{Event, EventKind, NAddr, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime};
+use crate::Error;
+
+/// The price of a classified listing
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListingPrice {
+    /// The amount, as written (e.g. "50" or "15.99")
+    pub amount: String,
+
+    /// The ISO 4217 currency code (e.g. "USD"), or another currency such as "BTC"
+    pub currency: String,
+
+    /// How often the price is paid (e.g. "month"), if it recurs
+    pub frequency: Option<String>,
+}
+
+/// Whether a classified listing is still available
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ListingStatus {
+    /// Available
+    Active,
+
+    /// No longer available
+    Sold,
+}
+
+impl ListingStatus {
+    /// As a str, as used in the 'status' tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingStatus::Active => "active",
+            ListingStatus::Sold => "sold",
+        }
+    }
+
+    /// From a str, as used in the 'status' tag
+    pub fn from_str_opt(s: &str) -> Option<ListingStatus> {
+        match s {
+            "active" => Some(ListingStatus::Active),
+            "sold" => Some(ListingStatus::Sold),
+            _ => None,
+        }
+    }
+}
+
+/// A NIP-99 classified listing (kind 30402), or a draft of one (kind 30403)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClassifiedListing {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The listing title
+    pub title: Option<String>,
+
+    /// A short tagline
+    pub summary: Option<String>,
+
+    /// When the listing was first published
+    pub published_at: Option<Unixtime>,
+
+    /// Where the item is
+    pub location: Option<String>,
+
+    /// The geohash of where the item is
+    pub geohash: Option<String>,
+
+    /// The price
+    pub price: Option<ListingPrice>,
+
+    /// Whether the item is still available
+    pub status: Option<ListingStatus>,
+
+    /// Images of the item
+    pub images: Vec<UncheckedUrl>,
+
+    /// Hashtags
+    pub hashtags: Vec<String>,
+
+    /// The description, in markdown
+    pub content: String,
+
+    /// Whether this is a draft
+    pub draft: bool,
+}
+
+impl ClassifiedListing {
+    /// Create a new classified listing
+    pub fn new(d: String, content: String) -> ClassifiedListing {
+        ClassifiedListing {
+            d,
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// The event kind of the listing
+    pub fn kind(&self) -> EventKind {
+        if self.draft {
+            EventKind::DraftClassifiedListing
+        } else {
+            EventKind::ClassifiedListing
+        }
+    }
+
+    /// The address of this listing, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: self.kind(),
+            author,
+        }
+    }
+
+    /// Parse a kind-30402 ClassifiedListing or kind-30403 DraftClassifiedListing event
+    pub fn from_event(event: &Event) -> Result<ClassifiedListing, Error> {
+        let draft = match event.kind {
+            EventKind::ClassifiedListing => false,
+            EventKind::DraftClassifiedListing => true,
+            _ => return Err(Error::WrongEventKind),
+        };
+
+        let mut listing = ClassifiedListing {
+            content: event.content.clone(),
+            draft,
+            ..Default::default()
+        };
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => listing.d = tag.value().to_owned(),
+                "title" => listing.title = Some(tag.value().to_owned()),
+                "summary" => listing.summary = Some(tag.value().to_owned()),
+                "published_at" => listing.published_at = Some(Unixtime(tag.value().parse()?)),
+                "location" => listing.location = Some(tag.value().to_owned()),
+                "g" => listing.geohash = Some(tag.value().to_owned()),
+                "price" => {
+                    listing.price = Some(ListingPrice {
+                        amount: tag.value().to_owned(),
+                        currency: tag.get_index(2).to_owned(),
+                        frequency: tag.get_opt_index(3).map(|f| f.to_owned()),
+                    })
+                }
+                "status" => listing.status = ListingStatus::from_str_opt(tag.value()),
+                "image" => listing.images.push(UncheckedUrl(tag.value().to_owned())),
+                "t" => listing.hashtags.push(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+        Ok(listing)
+    }
+
+    /// Create the kind-30402 ClassifiedListing (or kind-30403
+    /// DraftClassifiedListing) PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d])];
+        let strings = [
+            ("title", &self.title),
+            ("summary", &self.summary),
+            ("location", &self.location),
+            ("g", &self.geohash),
+        ];
+        for (name, value) in strings.iter() {
+            if let Some(value) = value {
+                tags.push(Tag::new(&[name, value]));
+            }
+        }
+        if let Some(published_at) = self.published_at {
+            tags.push(Tag::new(&["published_at", &published_at.0.to_string()]));
+        }
+        if let Some(price) = &self.price {
+            let mut tag = Tag::new(&["price", &price.amount, &price.currency]);
+            if let Some(frequency) = &price.frequency {
+                tag.set_index(3, frequency.clone());
+            }
+            tags.push(tag);
+        }
+        if let Some(status) = self.status {
+            tags.push(Tag::new(&["status", status.as_str()]));
+        }
+        for image in self.images.iter() {
+            tags.push(Tag::new(&["image", &image.0]));
+        }
+        for hashtag in self.hashtags.iter() {
+            tags.push(Tag::new(&["t", hashtag]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.kind(),
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_classified_listing_round_trip() {
+        let private_key = PrivateKey::generate();
+        let mut listing = ClassifiedListing::new(
+            "apartment".to_owned(),
+            "A sunny two bedroom apartment.".to_owned(),
+        );
+        listing.title = Some("Apartment for rent".to_owned());
+        listing.location = Some("Springfield".to_owned());
+        listing.price = Some(ListingPrice {
+            amount: "1500".to_owned(),
+            currency: "USD".to_owned(),
+            frequency: Some("month".to_owned()),
+        });
+        listing.status = Some(ListingStatus::Active);
+        listing
+            .images
+            .push(UncheckedUrl("https://example.com/apartment.jpg".to_owned()));
+        listing.hashtags.push("rent".to_owned());
+        let event = private_key
+            .sign_event(listing.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(event.kind, EventKind::ClassifiedListing);
+        assert_eq!(ClassifiedListing::from_event(&event).unwrap(), listing);
+    }
+}
@@ -0,0 +1,468 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+use std::collections::HashSet;
+
+/// A relay used by a community, with what it is used for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommunityRelay {
+    /// The relay
+    pub url: UncheckedUrl,
+
+    /// What the relay is used for ("author", "requests" or "approvals"), or None
+    /// for general use
+    pub marker: Option<String>,
+}
+
+/// A NIP-72 moderated community definition (kind 34550)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommunityDefinition {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The community name
+    pub name: Option<String>,
+
+    /// What the community is about
+    pub description: Option<String>,
+
+    /// The community image, with its width and height if known
+    pub image: Option<(UncheckedUrl, Option<(u32, u32)>)>,
+
+    /// The community rules
+    pub rules: Option<String>,
+
+    /// The moderators, with a relay where they may be found
+    pub moderators: Vec<(PublicKey, Option<UncheckedUrl>)>,
+
+    /// Relays used by the community
+    pub relays: Vec<CommunityRelay>,
+}
+
+impl CommunityDefinition {
+    /// Create a new community definition
+    pub fn new(d: String) -> CommunityDefinition {
+        CommunityDefinition {
+            d,
+            ..Default::default()
+        }
+    }
+
+    /// The address of this community, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: EventKind::CommunityDefinition,
+            author,
+        }
+    }
+
+    /// Whether someone is a moderator of the community
+    pub fn is_moderator(&self, pubkey: &PublicKey) -> bool {
+        self.moderators.iter().any(|(pk, _)| pk == pubkey)
+    }
+
+    /// Parse a kind-34550 CommunityDefinition event
+    pub fn from_event(event: &Event) -> Result<CommunityDefinition, Error> {
+        if event.kind != EventKind::CommunityDefinition {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut definition = CommunityDefinition::default();
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => definition.d = tag.value().to_owned(),
+                "name" => definition.name = Some(tag.value().to_owned()),
+                "description" => definition.description = Some(tag.value().to_owned()),
+                "rules" => definition.rules = Some(tag.value().to_owned()),
+                "image" => {
+                    let dimensions = tag.get_opt_index(2).and_then(|dim| {
+                        let (w, h) = dim.split_once('x')?;
+                        Some((w.parse().ok()?, h.parse().ok()?))
+                    });
+                    definition.image = Some((UncheckedUrl(tag.value().to_owned()), dimensions));
+                }
+                "relay" => definition.relays.push(CommunityRelay {
+                    url: UncheckedUrl(tag.value().to_owned()),
+                    marker: tag.get_opt_index(2).map(|s| s.to_owned()),
+                }),
+                "p" if tag.get_index(3) == "moderator" => {
+                    if let Ok(ParsedTag::Pubkey {
+                        pubkey,
+                        recommended_relay_url,
+                        ..
+                    }) = tag.parse()
+                    {
+                        definition.moderators.push((pubkey, recommended_relay_url));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(definition)
+    }
+
+    /// Create the kind-34550 CommunityDefinition PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d])];
+        if let Some(name) = &self.name {
+            tags.push(Tag::new(&["name", name]));
+        }
+        if let Some(description) = &self.description {
+            tags.push(Tag::new(&["description", description]));
+        }
+        if let Some(rules) = &self.rules {
+            tags.push(Tag::new(&["rules", rules]));
+        }
+        if let Some((url, dimensions)) = &self.image {
+            let mut tag = Tag::new(&["image", &url.0]);
+            if let Some((w, h)) = dimensions {
+                tag.set_index(2, format!("{w}x{h}"));
+            }
+            tags.push(tag);
+        }
+        for (moderator, relay) in self.moderators.iter() {
+            let mut tag = Tag::new(&["p", &moderator.as_hex_string()]);
+            tag.set_index(2, relay.as_ref().map(|r| r.0.clone()).unwrap_or_default());
+            tag.set_index(3, "moderator".to_owned());
+            tags.push(tag);
+        }
+        for relay in self.relays.iter() {
+            let mut tag = Tag::new(&["relay", &relay.url.0]);
+            if let Some(marker) = &relay.marker {
+                tag.set_index(2, marker.clone());
+            }
+            tags.push(tag);
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::CommunityDefinition,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+
+    /// The feed of the community published by `author`: posts to the community
+    /// approved by one of its moderators, newest first.
+    ///
+    /// An approval counts only if it is validly signed by a moderator, is for this
+    /// community, and its post (taken from `posts` by the approval's 'e' tag, or
+    /// else embedded in the approval) is validly signed and was posted to this
+    /// community.
+    pub fn approved_feed(
+        &self,
+        author: PublicKey,
+        posts: &[Event],
+        approvals: &[Event],
+    ) -> Vec<Event> {
+        let address = self.address(author);
+        let mut seen: HashSet<Id> = HashSet::new();
+        let mut feed: Vec<Event> = Vec::new();
+        for event in approvals.iter() {
+            if !self.is_moderator(&event.pubkey) || event.verify(None).is_err() {
+                continue;
+            }
+            let Ok(approval) = CommunityPostApproval::from_event(event) else {
+                continue;
+            };
+            if approval.community != address || seen.contains(&approval.post_id) {
+                continue;
+            }
+            let Some(post) = posts
+                .iter()
+                .find(|p| p.id == approval.post_id)
+                .or(approval.post.as_ref())
+            else {
+                continue;
+            };
+            if post.verify(None).is_err() {
+                continue;
+            }
+            // The post may be cross-posted to other communities too
+            if CommunityPost::from_event(post).is_err()
+                || !community_addresses(post).any(|a| a == address)
+            {
+                continue;
+            }
+            let _ = seen.insert(post.id);
+            feed.push(post.clone());
+        }
+        feed.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(a.id.cmp(&b.id)));
+        feed
+    }
+}
+
+/// A post to a NIP-72 community (a kind-1 text note tagging the community)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommunityPost {
+    /// The community
+    pub community: NAddr,
+
+    /// The post
+    pub content: String,
+}
+
+impl CommunityPost {
+    /// Create a new post to a community
+    pub fn new(community: NAddr, content: String) -> CommunityPost {
+        CommunityPost { community, content }
+    }
+
+    /// Parse a community post: any event other than a community definition or
+    /// approval that tags a community with an 'a' tag
+    pub fn from_event(event: &Event) -> Result<CommunityPost, Error> {
+        if event.kind == EventKind::CommunityDefinition
+            || event.kind == EventKind::CommunityPostApproval
+        {
+            return Err(Error::WrongEventKind);
+        }
+        let community = community_address(event).ok_or(Error::TagMismatch)?;
+        Ok(CommunityPost {
+            community,
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the kind-1 TextNote PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::TextNote,
+            tags: vec![ParsedTag::Address {
+                address: self.community.clone(),
+                marker: None,
+            }
+            .into_tag()],
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// A NIP-72 moderator's approval of a community post (kind 4550)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommunityPostApproval {
+    /// The community
+    pub community: NAddr,
+
+    /// The id of the approved post
+    pub post_id: Id,
+
+    /// The approved post, if it is embedded in the approval
+    pub post: Option<Event>,
+
+    /// A relay where the post may be found
+    pub post_relay: Option<UncheckedUrl>,
+}
+
+impl CommunityPostApproval {
+    /// Create a new approval of `post`, embedding it
+    pub fn new(community: NAddr, post: Event) -> CommunityPostApproval {
+        CommunityPostApproval {
+            community,
+            post_id: post.id,
+            post: Some(post),
+            post_relay: None,
+        }
+    }
+
+    /// Parse a kind-4550 CommunityPostApproval event. The embedded post, if there
+    /// is one, must match the 'e' tag.
+    pub fn from_event(event: &Event) -> Result<CommunityPostApproval, Error> {
+        if event.kind != EventKind::CommunityPostApproval {
+            return Err(Error::WrongEventKind);
+        }
+
+        let community = community_address(event).ok_or(Error::TagMismatch)?;
+        let (post_id, post_relay) = event
+            .tags
+            .iter()
+            .find_map(|tag| match tag.parse() {
+                Ok(ParsedTag::Event {
+                    id,
+                    recommended_relay_url,
+                    ..
+                }) => Some((id, recommended_relay_url)),
+                _ => None,
+            })
+            .ok_or(Error::TagMismatch)?;
+        let post = if event.content.is_empty() {
+            None
+        } else {
+            let post: Event = serde_json::from_str(&event.content)?;
+            if post.id != post_id {
+                return Err(Error::TagMismatch);
+            }
+            Some(post)
+        };
+
+        Ok(CommunityPostApproval {
+            community,
+            post_id,
+            post,
+            post_relay,
+        })
+    }
+
+    /// Create the kind-4550 CommunityPostApproval PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        let mut tags = vec![
+            ParsedTag::Address {
+                address: self.community.clone(),
+                marker: None,
+            }
+            .into_tag(),
+            ParsedTag::Event {
+                id: self.post_id,
+                recommended_relay_url: self.post_relay.clone(),
+                marker: None,
+                author_pubkey: None,
+            }
+            .into_tag(),
+        ];
+        let mut content = String::new();
+        if let Some(post) = &self.post {
+            tags.push(Tag::new(&["p", &post.pubkey.as_hex_string()]));
+            tags.push(Tag::new(&["k", &u32::from(post.kind).to_string()]));
+            content = serde_json::to_string(post)?;
+        }
+
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::CommunityPostApproval,
+            tags,
+            content,
+        })
+    }
+}
+
+fn community_address(event: &Event) -> Option<NAddr> {
+    community_addresses(event).next()
+}
+
+fn community_addresses(event: &Event) -> impl Iterator<Item = NAddr> + '_ {
+    event.tags.iter().filter_map(|tag| match tag.parse() {
+        Ok(ParsedTag::Address { address, .. })
+            if address.kind == EventKind::CommunityDefinition =>
+        {
+            Some(address)
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_community_definition() {
+        let private_key = PrivateKey::generate();
+        let mut definition = CommunityDefinition::new("rust".to_owned());
+        definition.name = Some("Rust".to_owned());
+        definition.rules = Some("Be kind".to_owned());
+        definition.image = Some((
+            UncheckedUrl("https://example.com/rust.png".to_owned()),
+            Some((256, 256)),
+        ));
+        definition.moderators.push((PublicKey::mock(), None));
+        definition.relays.push(CommunityRelay {
+            url: UncheckedUrl("wss://relay.example.com".to_owned()),
+            marker: Some("approvals".to_owned()),
+        });
+        let event = private_key
+            .sign_event(definition.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(CommunityDefinition::from_event(&event).unwrap(), definition);
+    }
+
+    #[test]
+    fn test_approved_feed() {
+        let owner = PrivateKey::generate();
+        let moderator = PrivateKey::generate();
+        let poster = PrivateKey::generate();
+
+        let mut definition = CommunityDefinition::new("rust".to_owned());
+        definition.moderators.push((moderator.public_key(), None));
+        let address = definition.address(owner.public_key());
+
+        let approved = CommunityPost::new(address.clone(), "approved".to_owned());
+        let approved = poster
+            .sign_event(approved.to_pre_event(poster.public_key()))
+            .unwrap();
+        // Cross-posted, with another community tagged first
+        let other = CommunityDefinition::new("go".to_owned()).address(owner.public_key());
+        let mut cross_posted =
+            CommunityPost::new(other, "cross-posted".to_owned()).to_pre_event(poster.public_key());
+        cross_posted.tags.push(
+            ParsedTag::Address {
+                address: address.clone(),
+                marker: None,
+            }
+            .into_tag(),
+        );
+        cross_posted.created_at = Unixtime(approved.created_at.0 - 1);
+        let cross_posted = poster.sign_event(cross_posted).unwrap();
+        let unapproved = CommunityPost::new(address.clone(), "unapproved".to_owned());
+        let unapproved = poster
+            .sign_event(unapproved.to_pre_event(poster.public_key()))
+            .unwrap();
+
+        let approval = CommunityPostApproval::new(address.clone(), approved.clone());
+        let approval_event = moderator
+            .sign_event(approval.to_pre_event(moderator.public_key()).unwrap())
+            .unwrap();
+        assert_eq!(
+            CommunityPostApproval::from_event(&approval_event).unwrap(),
+            approval
+        );
+
+        let cross_approval = CommunityPostApproval::new(address.clone(), cross_posted.clone());
+        let cross_approval_event = moderator
+            .sign_event(cross_approval.to_pre_event(moderator.public_key()).unwrap())
+            .unwrap();
+
+        // An approval that only references the post by id
+        let late = CommunityPost::new(address.clone(), "late".to_owned());
+        let mut late = late.to_pre_event(poster.public_key());
+        late.created_at = Unixtime(approved.created_at.0 - 2);
+        let late = poster.sign_event(late).unwrap();
+        let mut id_only = CommunityPostApproval::new(address.clone(), late.clone());
+        id_only.post = None;
+        let id_only_event = moderator
+            .sign_event(id_only.to_pre_event(moderator.public_key()).unwrap())
+            .unwrap();
+        assert!(id_only_event.content.is_empty());
+        assert_eq!(
+            CommunityPostApproval::from_event(&id_only_event).unwrap(),
+            id_only
+        );
+
+        // An approval by someone who is not a moderator
+        let approval = CommunityPostApproval::new(address, unapproved.clone());
+        let bogus_event = poster
+            .sign_event(approval.to_pre_event(poster.public_key()).unwrap())
+            .unwrap();
+
+        let approvals = vec![
+            approval_event,
+            cross_approval_event,
+            id_only_event,
+            bogus_event,
+        ];
+        let feed = definition.approved_feed(owner.public_key(), &[], &approvals);
+        assert_eq!(feed, vec![approved.clone(), cross_posted.clone()]);
+        let feed = definition.approved_feed(
+            owner.public_key(),
+            &[approved.clone(), late.clone(), unapproved],
+            &approvals,
+        );
+        assert_eq!(feed, vec![approved, cross_posted, late]);
+    }
+}
@@ -0,0 +1,384 @@
+use crate::types::{Event, EventKind, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime};
+use crate::Error;
+use std::collections::HashSet;
+
+/// A followed person in a ContactList
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Contact {
+    /// The followed person
+    pub pubkey: PublicKey,
+
+    /// A relay where they may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// A local name for them
+    pub petname: Option<String>,
+}
+
+impl Contact {
+    fn to_tag(&self) -> Tag {
+        ParsedTag::Pubkey {
+            pubkey: self.pubkey,
+            recommended_relay_url: self.relay.clone(),
+            petname: self.petname.clone(),
+        }
+        .into_tag()
+    }
+}
+
+// Tags are kept in their original order. Tags that are not contacts (or are
+// duplicate or malformed contacts) are kept as they were, as are contact tags
+// until the contact is changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ContactListItem {
+    Contact(Contact, Option<Tag>),
+    Other(Tag),
+}
+
+/// A follow list, from a kind-3 ContactList event
+///
+/// Ordering is preserved, as are tags that are not contacts and the content
+/// (which some clients still use for a legacy relay list).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContactList {
+    items: Vec<ContactListItem>,
+
+    /// The event content, usually empty or a legacy JSON relay list
+    pub content: String,
+}
+
+/// The follows added and removed between two ContactLists
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ContactListDiff {
+    /// Follows in the new list but not the old one
+    pub added: Vec<PublicKey>,
+
+    /// Follows in the old list but not the new one
+    pub removed: Vec<PublicKey>,
+}
+
+impl ContactListDiff {
+    /// Whether the lists follow the same people
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// The outcome of `ContactList::safe_merge()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContactListMerge {
+    /// The list to use
+    pub list: ContactList,
+
+    /// How the newer list differs from the older one
+    pub diff: ContactListDiff,
+
+    /// Whether the newer list dropped too many follows, in which case `list` is
+    /// the newer list with the dropped follows restored
+    pub clobbered: bool,
+}
+
+impl ContactList {
+    /// Create a new empty ContactList
+    pub fn new() -> ContactList {
+        Default::default()
+    }
+
+    /// Parse a kind-3 ContactList event
+    pub fn from_event(event: &Event) -> Result<ContactList, Error> {
+        if event.kind != EventKind::ContactList {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut seen: HashSet<PublicKey> = HashSet::new();
+        let mut items: Vec<ContactListItem> = Vec::with_capacity(event.tags.len());
+        for tag in event.tags.iter() {
+            if let Ok(ParsedTag::Pubkey {
+                pubkey,
+                recommended_relay_url,
+                petname,
+            }) = tag.parse()
+            {
+                if seen.insert(pubkey) {
+                    items.push(ContactListItem::Contact(
+                        Contact {
+                            pubkey,
+                            relay: recommended_relay_url,
+                            petname,
+                        },
+                        Some(tag.clone()),
+                    ));
+                    continue;
+                }
+            }
+            items.push(ContactListItem::Other(tag.clone()));
+        }
+
+        Ok(ContactList {
+            items,
+            content: event.content.clone(),
+        })
+    }
+
+    /// The contacts, in order
+    pub fn contacts(&self) -> impl Iterator<Item = &Contact> {
+        self.items.iter().filter_map(|item| match item {
+            ContactListItem::Contact(c, _) => Some(c),
+            ContactListItem::Other(_) => None,
+        })
+    }
+
+    /// The followed public keys, in order
+    pub fn pubkeys(&self) -> Vec<PublicKey> {
+        self.contacts().map(|c| c.pubkey).collect()
+    }
+
+    /// Tags that are not contacts
+    pub fn other_tags(&self) -> impl Iterator<Item = &Tag> {
+        self.items.iter().filter_map(|item| match item {
+            ContactListItem::Contact(..) => None,
+            ContactListItem::Other(t) => Some(t),
+        })
+    }
+
+    /// The number of contacts
+    pub fn len(&self) -> usize {
+        self.contacts().count()
+    }
+
+    /// Whether there are no contacts
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get a contact
+    pub fn get(&self, pubkey: &PublicKey) -> Option<&Contact> {
+        self.contacts().find(|c| c.pubkey == *pubkey)
+    }
+
+    /// Whether a person is followed
+    pub fn contains(&self, pubkey: &PublicKey) -> bool {
+        self.get(pubkey).is_some()
+    }
+
+    /// Add a contact at the end, or update the relay and petname of an existing
+    /// contact in place. Returns true if the contact is new.
+    pub fn add(&mut self, contact: Contact) -> bool {
+        for item in self.items.iter_mut() {
+            if let ContactListItem::Contact(c, tag) = item {
+                if c.pubkey == contact.pubkey {
+                    if *c != contact {
+                        *c = contact;
+                        *tag = None;
+                    }
+                    return false;
+                }
+            }
+        }
+        self.items.push(ContactListItem::Contact(contact, None));
+        true
+    }
+
+    /// Remove a contact. Returns true if they were followed.
+    pub fn remove(&mut self, pubkey: &PublicKey) -> bool {
+        let before = self.items.len();
+        self.items.retain(|item| match item {
+            ContactListItem::Contact(c, _) => c.pubkey != *pubkey,
+            ContactListItem::Other(_) => true,
+        });
+        before != self.items.len()
+    }
+
+    /// The legacy relay list from the content, as (url, read, write).
+    ///
+    /// Returns an empty list if the content is empty or not a relay list.
+    pub fn legacy_relays(&self) -> Vec<(UncheckedUrl, bool, bool)> {
+        let map: serde_json::Map<String, serde_json::Value> =
+            match serde_json::from_str(&self.content) {
+                Ok(m) => m,
+                Err(_) => return vec![],
+            };
+        map.into_iter()
+            .map(|(url, usage)| {
+                let read = usage.get("read").and_then(|v| v.as_bool()).unwrap_or(false);
+                let write = usage
+                    .get("write")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                (UncheckedUrl(url), read, write)
+            })
+            .collect()
+    }
+
+    /// Create the tags for a kind-3 ContactList event, in order
+    pub fn to_event_tags(&self) -> Vec<Tag> {
+        self.items
+            .iter()
+            .map(|item| match item {
+                ContactListItem::Contact(_, Some(t)) => t.clone(),
+                ContactListItem::Contact(c, None) => c.to_tag(),
+                ContactListItem::Other(t) => t.clone(),
+            })
+            .collect()
+    }
+
+    /// Create a kind-3 ContactList PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ContactList,
+            tags: self.to_event_tags(),
+            content: self.content.clone(),
+        }
+    }
+
+    /// The follows added and removed going from `old` to `new`, each in list order
+    pub fn diff(old: &ContactList, new: &ContactList) -> ContactListDiff {
+        let old_set: HashSet<PublicKey> = old.contacts().map(|c| c.pubkey).collect();
+        let new_set: HashSet<PublicKey> = new.contacts().map(|c| c.pubkey).collect();
+        ContactListDiff {
+            added: new
+                .contacts()
+                .map(|c| c.pubkey)
+                .filter(|pk| !old_set.contains(pk))
+                .collect(),
+            removed: old
+                .contacts()
+                .map(|c| c.pubkey)
+                .filter(|pk| !new_set.contains(pk))
+                .collect(),
+        }
+    }
+
+    /// Decide whether a newer contact list can safely replace an older one.
+    ///
+    /// A newer list which drops more than `max_removed_fraction` (0.0 to 1.0) of
+    /// the older list's follows is probably a clobbered list, written by a client
+    /// that never saw the older one. In that case the result is the newer list with
+    /// the dropped follows added back at the end, and `clobbered` is set so the
+    /// caller can ask the user. Otherwise the result is the newer list.
+    pub fn safe_merge(
+        older: &ContactList,
+        newer: &ContactList,
+        max_removed_fraction: f64,
+    ) -> ContactListMerge {
+        let diff = ContactList::diff(older, newer);
+        let clobbered = !older.is_empty()
+            && (diff.removed.len() as f64) > max_removed_fraction * (older.len() as f64);
+
+        let mut list = newer.clone();
+        if clobbered {
+            for pubkey in diff.removed.iter() {
+                if let Some(contact) = older.get(pubkey) {
+                    let _ = list.add(contact.clone());
+                }
+            }
+        }
+
+        ContactListMerge {
+            list,
+            diff,
+            clobbered,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_contact_list_round_trip() {
+        let private_key = PrivateKey::generate();
+        let a = PublicKey::mock();
+        let b = PublicKey::mock();
+        let c = PublicKey::mock();
+        let content = r#"{"wss://relay.example.com":{"read":true,"write":false}}"#;
+        let tags = vec![
+            Tag::new(&["p", &a.as_hex_string(), "wss://a.example.com/", "alice"]),
+            Tag::new(&["t", "nostr"]),
+            Tag::new(&["p", &b.as_hex_string(), "", ""]),
+            Tag::new(&["p", &c.as_hex_string(), "", "carol", "extra"]),
+            Tag::new(&["p", "not-a-pubkey"]),
+        ];
+        let event = private_key
+            .sign_event(PreEvent {
+                pubkey: private_key.public_key(),
+                created_at: Unixtime::mock(),
+                kind: EventKind::ContactList,
+                tags: tags.clone(),
+                content: content.to_owned(),
+            })
+            .unwrap();
+
+        let list = ContactList::from_event(&event).unwrap();
+        assert_eq!(list.pubkeys(), vec![a, b, c]);
+        assert_eq!(list.get(&a).unwrap().petname.as_deref(), Some("alice"));
+        assert_eq!(list.other_tags().count(), 2);
+        assert_eq!(
+            list.legacy_relays(),
+            vec![(
+                UncheckedUrl("wss://relay.example.com".to_owned()),
+                true,
+                false
+            )]
+        );
+
+        let pre_event = list.to_pre_event(private_key.public_key());
+        assert_eq!(pre_event.tags, tags);
+        assert_eq!(pre_event.content, content);
+
+        // Re-adding a contact unchanged keeps its tag, changing it rebuilds it
+        let mut list = list;
+        let carol = list.get(&c).unwrap().clone();
+        assert!(!list.add(carol.clone()));
+        assert_eq!(list.to_event_tags(), tags);
+        assert!(!list.add(Contact {
+            petname: Some("caroline".to_owned()),
+            ..carol
+        }));
+        assert_eq!(
+            list.to_event_tags()[3],
+            Tag::new(&["p", &c.as_hex_string(), "", "caroline"])
+        );
+    }
+
+    #[test]
+    fn test_contact_list_diff_and_safe_merge() {
+        let pubkeys: Vec<PublicKey> = (0..10).map(|_| PublicKey::mock()).collect();
+        let contact = |pubkey: PublicKey| Contact {
+            pubkey,
+            relay: None,
+            petname: None,
+        };
+
+        let mut older = ContactList::new();
+        for pk in pubkeys.iter() {
+            let _ = older.add(contact(*pk));
+        }
+
+        let mut newer = older.clone();
+        assert!(newer.remove(&pubkeys[0]));
+        let extra = PublicKey::mock();
+        assert!(newer.add(contact(extra)));
+
+        let diff = ContactList::diff(&older, &newer);
+        assert_eq!(diff.added, vec![extra]);
+        assert_eq!(diff.removed, vec![pubkeys[0]]);
+
+        let merge = ContactList::safe_merge(&older, &newer, 0.5);
+        assert!(!merge.clobbered);
+        assert_eq!(merge.list, newer);
+
+        // A client that only knew about one follow
+        let mut clobber = ContactList::new();
+        let _ = clobber.add(contact(extra));
+        let merge = ContactList::safe_merge(&older, &clobber, 0.5);
+        assert!(merge.clobbered);
+        assert_eq!(merge.diff.removed.len(), 10);
+        assert_eq!(merge.list.len(), 11);
+        assert_eq!(merge.list.pubkeys()[0], extra);
+    }
+}
@@ -14,6 +14,21 @@ pub struct Span {
 }
 
 impl Span {
+    /// Create a span over bytes `start..end`
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// The byte offset where the span starts
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// The byte offset where the span ends
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
     /// Modify a span by offsetting it from the start by `offset` bytes
     pub fn offset(&mut self, offset: usize) {
         self.start += offset;
@@ -37,6 +52,46 @@ pub enum ContentSegment {
     Plain(Span),
 }
 
+/// A segment of markdown content
+#[derive(Clone, Debug)]
+pub enum MarkdownSegment {
+    /// Content outside of markdown constructs, such as plain text or a Nostr URL
+    Content(ContentSegment),
+
+    /// A heading, with its level (1 to 6) and text
+    Heading(u8, Span),
+
+    /// A link
+    Link {
+        /// The link text
+        text: Span,
+
+        /// The link destination
+        url: Span,
+    },
+
+    /// An image
+    Image {
+        /// The alternative text
+        alt: Span,
+
+        /// The image url
+        url: Span,
+    },
+
+    /// A fenced code block
+    CodeBlock {
+        /// The language given after the opening fence, if any
+        language: Option<Span>,
+
+        /// The code, without the fences
+        code: Span,
+    },
+
+    /// Inline code, without the backticks
+    InlineCode(Span),
+}
+
 /// A sequence of content segments
 #[derive(Clone, Debug)]
 pub struct ShatteredContent {
@@ -80,6 +135,44 @@ impl ShatteredContent {
     }
 }
 
+/// A sequence of markdown segments
+#[derive(Clone, Debug)]
+pub struct ShatteredMarkdown {
+    /// The sequence of `MarkdownSegment`s
+    pub segments: Vec<MarkdownSegment>,
+
+    /// The original content (the allocated string)
+    /// `Range`s within segments refer to this
+    pub allocated: String,
+}
+
+impl ShatteredMarkdown {
+    /// Break markdown content, such as a NIP-23 article, into meaningful segments
+    ///
+    /// Headings, links, images and code are recognized as well as everything
+    /// `ShatteredContent` recognizes. Code is kept whole, so `#[n]` and `nostr:`
+    /// inside code are not taken as references.
+    pub fn new(content: String) -> ShatteredMarkdown {
+        let segments = shatter_markdown(&content);
+
+        ShatteredMarkdown {
+            segments,
+            allocated: content,
+        }
+    }
+
+    /// View a slice of the original content as specified in a Span
+    #[allow(clippy::string_slice)] // the Span is trusted
+    pub fn slice<'a>(&'a self, span: &Span) -> Option<&'a str> {
+        if self.allocated.is_char_boundary(span.start) && self.allocated.is_char_boundary(span.end)
+        {
+            Some(&self.allocated[span.start..span.end])
+        } else {
+            None
+        }
+    }
+}
+
 /// Break content into a linear sequence of `ContentSegment`s
 #[allow(clippy::string_slice)] // start/end from find_nostr_url_pos is trusted
 fn shatter_content_1(mut content: &str) -> Vec<ContentSegment> {
@@ -159,6 +252,176 @@ fn shatter_content_3(content: &str) -> Vec<ContentSegment> {
     segments
 }
 
+/// Break markdown content into a linear sequence of `MarkdownSegment`s
+///
+/// Headings and fenced code blocks are found line by line. The text between them
+/// is broken up by `shatter_markdown_inline()`.
+#[allow(clippy::string_slice)] // line positions are trusted
+fn shatter_markdown(content: &str) -> Vec<MarkdownSegment> {
+    let mut segments: Vec<MarkdownSegment> = Vec::new();
+    let mut text_start: usize = 0; // start of the text not yet segmented
+    let mut pos: usize = 0;
+
+    // The open fence character and length, the language, and where the code starts
+    let mut fence: Option<(char, usize, Option<Span>, usize)> = None;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = pos;
+        pos += line.len();
+        let trimmed = line.trim_end();
+        let body = trimmed.trim_start();
+        if trimmed.len() - body.len() >= 4 {
+            // Indented: not a fence or a heading
+            continue;
+        }
+
+        if let Some((fence_char, fence_len, language, code_start)) = fence {
+            if body.len() >= fence_len && body.chars().all(|c| c == fence_char) {
+                segments.push(MarkdownSegment::CodeBlock {
+                    language,
+                    code: Span {
+                        start: code_start,
+                        end: line_start,
+                    },
+                });
+                fence = None;
+                text_start = pos;
+            }
+            continue;
+        }
+
+        let fence_char = if body.starts_with("```") {
+            '`'
+        } else if body.starts_with("~~~") {
+            '~'
+        } else {
+            ' '
+        };
+        if fence_char != ' ' {
+            let info = body.trim_start_matches(fence_char);
+            let fence_len = body.len() - info.len();
+            let info = info.trim_start();
+            if fence_char == '~' || !info.contains('`') {
+                push_markdown_text(&mut segments, content, text_start, line_start);
+                let language = if info.is_empty() {
+                    None
+                } else {
+                    Some(subspan(content, info))
+                };
+                fence = Some((fence_char, fence_len, language, pos));
+                continue;
+            }
+        }
+
+        let text = body.trim_start_matches('#');
+        let level = body.len() - text.len();
+        if (1..=6).contains(&level) && (text.is_empty() || text.starts_with([' ', '\t'])) {
+            push_markdown_text(&mut segments, content, text_start, line_start);
+            let text = text.trim();
+            // An optional closing sequence of '#'s
+            let text = match text.trim_end_matches('#') {
+                t if t.is_empty() || t.ends_with([' ', '\t']) => t.trim_end(),
+                _ => text,
+            };
+            segments.push(MarkdownSegment::Heading(
+                level as u8,
+                subspan(content, text),
+            ));
+            text_start = pos;
+        }
+    }
+
+    if let Some((_, _, language, code_start)) = fence {
+        // An unclosed code block runs to the end
+        segments.push(MarkdownSegment::CodeBlock {
+            language,
+            code: Span {
+                start: code_start,
+                end: content.len(),
+            },
+        });
+    } else {
+        push_markdown_text(&mut segments, content, text_start, content.len());
+    }
+
+    segments
+}
+
+#[allow(clippy::string_slice)] // start/end are line positions, which are trusted
+fn push_markdown_text(
+    segments: &mut Vec<MarkdownSegment>,
+    content: &str,
+    start: usize,
+    end: usize,
+) {
+    let mut inner_segments = shatter_markdown_inline(&content[start..end]);
+    apply_markdown_offset(&mut inner_segments, start);
+    segments.append(&mut inner_segments);
+}
+
+// Inline code, images and links, with everything else going through
+// `shatter_content_1()`
+#[allow(clippy::string_slice)] // Regex positions are trusted
+fn shatter_markdown_inline(content: &str) -> Vec<MarkdownSegment> {
+    lazy_static! {
+        static ref INLINE_RE: Regex = Regex::new(
+            r"(?P<code>`[^`\n]+`)|(?P<bang>!)?\[(?P<text>[^\]\n]*)\]\((?P<url>[^)\s]+)\)"
+        )
+        .unwrap();
+    }
+
+    let mut segments: Vec<MarkdownSegment> = Vec::new();
+
+    let mut pos = 0;
+    for caps in INLINE_RE.captures_iter(content) {
+        // If panics on unwrap, something is wrong with Regex.
+        let mat = caps.get(0).unwrap();
+        push_content(&mut segments, &content[pos..mat.start()], pos);
+
+        if let Some(code) = caps.name("code") {
+            segments.push(MarkdownSegment::InlineCode(Span {
+                start: code.start() + 1,
+                end: code.end() - 1,
+            }));
+        } else if let (Some(text), Some(url)) = (caps.name("text"), caps.name("url")) {
+            let text = Span {
+                start: text.start(),
+                end: text.end(),
+            };
+            let url = Span {
+                start: url.start(),
+                end: url.end(),
+            };
+            if caps.name("bang").is_some() {
+                segments.push(MarkdownSegment::Image { alt: text, url });
+            } else {
+                segments.push(MarkdownSegment::Link { text, url });
+            }
+        }
+        pos = mat.end();
+    }
+
+    push_content(&mut segments, &content[pos..], pos);
+
+    segments
+}
+
+// Non-markdown content, through `shatter_content_1()`
+fn push_content(segments: &mut Vec<MarkdownSegment>, content: &str, offset: usize) {
+    let mut inner_segments = shatter_content_1(content);
+    apply_offset(&mut inner_segments, offset);
+    segments.extend(inner_segments.into_iter().map(MarkdownSegment::Content));
+}
+
+// The Span of `sub` within `content`, of which it must be a subslice
+fn subspan(content: &str, sub: &str) -> Span {
+    let start = sub.as_ptr() as usize - content.as_ptr() as usize;
+    Span {
+        start,
+        end: start + sub.len(),
+    }
+}
+
 fn apply_offset(segments: &mut [ContentSegment], offset: usize) {
     for segment in segments.iter_mut() {
         match segment {
@@ -169,6 +432,32 @@ fn apply_offset(segments: &mut [ContentSegment], offset: usize) {
     }
 }
 
+fn apply_markdown_offset(segments: &mut [MarkdownSegment], offset: usize) {
+    for segment in segments.iter_mut() {
+        match segment {
+            MarkdownSegment::Content(segment) => {
+                apply_offset(std::slice::from_mut(segment), offset)
+            }
+            MarkdownSegment::Heading(_, span) => span.offset(offset),
+            MarkdownSegment::Link { text, url } => {
+                text.offset(offset);
+                url.offset(offset);
+            }
+            MarkdownSegment::Image { alt, url } => {
+                alt.offset(offset);
+                url.offset(offset);
+            }
+            MarkdownSegment::CodeBlock { language, code } => {
+                if let Some(language) = language {
+                    language.offset(offset);
+                }
+                code.offset(offset);
+            }
+            MarkdownSegment::InlineCode(span) => span.offset(offset),
+        }
+    }
+}
+
 
 fn replace_urls_with_nostr(content: &str) -> String {
     const PATTERNS: &[(&'static str, &'static str)] = &[
@@ -263,4 +552,57 @@ Wonderful Long Weekend at a Zitadelle, Here Impressions opsec included
         assert_eq!(pieces.segments.len(), 2);
         assert!(matches!(pieces.segments[1], ContentSegment::NostrUrl(_)));
     }
+
+    #[test]
+    fn test_shatter_markdown() {
+        let content_str = "# Title #
+
+Intro with [a link](https://example.com) and ![a picture](https://example.com/a.png)
+and `#[0]` inline, then #[1] and nostr:npub1acg6thl5psv62405rljzkj8spesceyfz2c32udakc2ak0dmvfeyse9p35c
+
+```rust
+let x = v#[0];
+```
+## Done";
+        let pieces = ShatteredMarkdown::new(content_str.to_string());
+        let segments = &pieces.segments;
+        assert_eq!(segments.len(), 14);
+        if let MarkdownSegment::Heading(level, span) = segments[0] {
+            assert_eq!(level, 1);
+            assert_eq!(pieces.slice(&span), Some("Title"));
+        } else {
+            panic!("Expected a heading");
+        }
+        assert!(matches!(
+            segments[1],
+            MarkdownSegment::Content(ContentSegment::Plain(..))
+        ));
+        if let MarkdownSegment::Link { text, url } = segments[2] {
+            assert_eq!(pieces.slice(&text), Some("a link"));
+            assert_eq!(pieces.slice(&url), Some("https://example.com"));
+        } else {
+            panic!("Expected a link");
+        }
+        assert!(matches!(segments[4], MarkdownSegment::Image { .. }));
+        if let MarkdownSegment::InlineCode(span) = segments[6] {
+            assert_eq!(pieces.slice(&span), Some("#[0]"));
+        } else {
+            panic!("Expected inline code");
+        }
+        assert!(matches!(
+            segments[8],
+            MarkdownSegment::Content(ContentSegment::TagReference(1))
+        ));
+        assert!(matches!(
+            segments[10],
+            MarkdownSegment::Content(ContentSegment::NostrUrl(..))
+        ));
+        if let MarkdownSegment::CodeBlock { language, code } = segments[12] {
+            assert_eq!(pieces.slice(&language.unwrap()), Some("rust"));
+            assert_eq!(pieces.slice(&code), Some("let x = v#[0];\n"));
+        } else {
+            panic!("Expected a code block");
+        }
+        assert!(matches!(segments[13], MarkdownSegment::Heading(2, _)));
+    }
 }
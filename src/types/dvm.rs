@@ -0,0 +1,735 @@
+use crate::types::{
+    ContentEncryptionAlgorithm, Event, EventKind, Id, MilliSatoshi, ParsedTag, PreEvent, PublicKey,
+    Signer, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+
+/// The type of a job input (the third field of an 'i' tag)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobInputType {
+    /// The data is the input
+    Text,
+
+    /// The data is a URL to fetch the input from
+    Url,
+
+    /// The data is the id of an event
+    Event,
+
+    /// The data is the id of a job request whose result is the input
+    Job,
+}
+
+impl JobInputType {
+    /// The 'i' tag value
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            JobInputType::Text => "text",
+            JobInputType::Url => "url",
+            JobInputType::Event => "event",
+            JobInputType::Job => "job",
+        }
+    }
+
+    /// Parse an 'i' tag value
+    pub fn from_str_opt(s: &str) -> Option<JobInputType> {
+        match s {
+            "text" => Some(JobInputType::Text),
+            "url" => Some(JobInputType::Url),
+            "event" => Some(JobInputType::Event),
+            "job" => Some(JobInputType::Job),
+            _ => None,
+        }
+    }
+}
+
+/// An input to a job
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobInput {
+    /// The data, interpreted according to the input type
+    pub data: String,
+
+    /// The input type
+    pub input_type: JobInputType,
+
+    /// A relay where the event or job result may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// How the input is to be used by the job
+    pub marker: Option<String>,
+}
+
+impl JobInput {
+    /// Create a new input
+    pub fn new(data: String, input_type: JobInputType) -> JobInput {
+        JobInput {
+            data,
+            input_type,
+            relay: None,
+            marker: None,
+        }
+    }
+
+    /// Parse an 'i' tag. Returns None if the type is missing or unknown.
+    pub fn from_tag(tag: &Tag) -> Option<JobInput> {
+        if tag.tagname() != "i" {
+            return None;
+        }
+        Some(JobInput {
+            data: tag.value().to_owned(),
+            input_type: JobInputType::from_str_opt(tag.get_opt_index(2)?)?,
+            relay: tag.get_opt_index(3).map(|s| UncheckedUrl(s.to_owned())),
+            marker: tag.get_opt_index(4).map(|s| s.to_owned()),
+        })
+    }
+
+    /// Create the 'i' tag
+    pub fn to_tag(&self) -> Tag {
+        let mut tag = Tag::new(&["i", &self.data, self.input_type.as_str()]);
+        if self.relay.is_some() || self.marker.is_some() {
+            tag.set_index(
+                3,
+                self.relay.as_ref().map(|r| r.0.clone()).unwrap_or_default(),
+            );
+        }
+        if let Some(marker) = &self.marker {
+            tag.set_index(4, marker.clone());
+        }
+        tag
+    }
+}
+
+/// A NIP-90 Data Vending Machine job request (kinds 5000-5999)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobRequest {
+    /// The kind of job
+    pub kind: EventKind,
+
+    /// The inputs
+    pub inputs: Vec<JobInput>,
+
+    /// The mime type the result should be in
+    pub output: Option<String>,
+
+    /// Job parameters, as (name, value)
+    pub params: Vec<(String, String)>,
+
+    /// The most the customer will pay. A bid that is not a number is ignored.
+    pub bid: Option<MilliSatoshi>,
+
+    /// Relays where service providers should publish responses
+    pub relays: Vec<UncheckedUrl>,
+
+    /// The service providers the customer wants to hear from. Empty means any.
+    pub service_providers: Vec<PublicKey>,
+
+    // Encrypted inputs and params that have not been decrypted
+    encrypted: Option<String>,
+}
+
+impl JobRequest {
+    /// Create a new job request. Fails if `kind` is not a job request kind.
+    pub fn new(kind: EventKind) -> Result<JobRequest, Error> {
+        if !kind.is_job_request() {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(JobRequest {
+            kind,
+            inputs: vec![],
+            output: None,
+            params: vec![],
+            bid: None,
+            relays: vec![],
+            service_providers: vec![],
+            encrypted: None,
+        })
+    }
+
+    /// Read a job request from an event. Encrypted inputs and params stay
+    /// encrypted until `decrypt()` is called.
+    pub fn from_event(event: &Event) -> Result<JobRequest, Error> {
+        let mut request = JobRequest::new(event.kind)?;
+        let mut encrypted = false;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "output" => request.output = Some(tag.value().to_owned()),
+                "bid" => request.bid = tag.value().parse::<u64>().ok().map(MilliSatoshi),
+                "relays" => {
+                    for i in 1..tag.len() {
+                        request
+                            .relays
+                            .push(UncheckedUrl(tag.get_index(i).to_owned()));
+                    }
+                }
+                "p" => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        request.service_providers.push(pubkey);
+                    }
+                }
+                "encrypted" => encrypted = true,
+                _ => request.read_input_or_param(tag),
+            }
+        }
+        if encrypted {
+            request.encrypted = Some(event.content.clone());
+        }
+        Ok(request)
+    }
+
+    fn read_input_or_param(&mut self, tag: &Tag) {
+        match tag.tagname() {
+            "i" => {
+                if let Some(input) = JobInput::from_tag(tag) {
+                    self.inputs.push(input);
+                }
+            }
+            "param" => {
+                if let Some(value) = tag.get_opt_index(2) {
+                    self.params.push((tag.value().to_owned(), value.to_owned()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the inputs and params are still encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    /// Decrypt the inputs and params. `counterparty` is the service provider when
+    /// the customer decrypts, and the customer when the service provider does.
+    pub fn decrypt<S>(&mut self, signer: &S, counterparty: &PublicKey) -> Result<(), Error>
+    where
+        S: Signer,
+    {
+        if let Some(content) = &self.encrypted {
+            let plaintext = signer.decrypt(counterparty, content)?;
+            let tags: Vec<Tag> = serde_json::from_str(&plaintext)?;
+            for tag in tags.iter() {
+                self.read_input_or_param(tag);
+            }
+            self.encrypted = None;
+        }
+        Ok(())
+    }
+
+    fn input_and_param_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = self.inputs.iter().map(|i| i.to_tag()).collect();
+        for (name, value) in self.params.iter() {
+            tags.push(Tag::new(&["param", name, value]));
+        }
+        tags
+    }
+
+    fn other_tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(output) = &self.output {
+            tags.push(Tag::new(&["output", output]));
+        }
+        if let Some(bid) = self.bid {
+            tags.push(Tag::new(&["bid", &bid.0.to_string()]));
+        }
+        if !self.relays.is_empty() {
+            let mut tag = Tag::new(&["relays"]);
+            tag.push_values(self.relays.iter().map(|r| r.0.clone()).collect());
+            tags.push(tag);
+        }
+        for pubkey in self.service_providers.iter() {
+            tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+        }
+        tags
+    }
+
+    /// Create the job request PreEvent, with inputs and params in the clear
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = self.input_and_param_tags();
+        tags.extend(self.other_tags());
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.kind,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+
+    /// Create the job request PreEvent with the inputs and params encrypted
+    /// (NIP-04) to a single service provider
+    pub fn to_encrypted_pre_event<S>(
+        &self,
+        signer: &S,
+        service_provider: PublicKey,
+    ) -> Result<PreEvent, Error>
+    where
+        S: Signer,
+    {
+        let plaintext = serde_json::to_string(&self.input_and_param_tags())?;
+        let content = signer.encrypt(
+            &service_provider,
+            &plaintext,
+            ContentEncryptionAlgorithm::Nip04,
+        )?;
+
+        let mut request = self.clone();
+        request.service_providers = vec![service_provider];
+        let mut tags = request.other_tags();
+        tags.push(Tag::new(&["encrypted"]));
+
+        Ok(PreEvent {
+            pubkey: signer.public_key(),
+            created_at: Unixtime::now(),
+            kind: self.kind,
+            tags,
+            content,
+        })
+    }
+
+    /// Resolve chained jobs: replace each 'job' input with a 'text' input holding
+    /// the content of that job's result, found in `results`.
+    ///
+    /// Fails if any chained job has no result yet.
+    pub fn resolve_inputs(&self, results: &[Event]) -> Result<Vec<JobInput>, Error> {
+        let mut output: Vec<JobInput> = Vec::with_capacity(self.inputs.len());
+        for input in self.inputs.iter() {
+            if input.input_type != JobInputType::Job {
+                output.push(input.clone());
+                continue;
+            }
+            let id = Id::try_from_hex_string(&input.data)?;
+            let result = results
+                .iter()
+                .filter_map(|e| JobResult::from_event(e).ok())
+                .find(|r| r.request_id == id)
+                .ok_or_else(|| Error::Dvm(format!("No result for job {}", input.data)))?;
+            output.push(JobInput {
+                data: result.content,
+                input_type: JobInputType::Text,
+                relay: None,
+                marker: input.marker.clone(),
+            });
+        }
+        Ok(output)
+    }
+}
+
+/// A NIP-90 job result (kinds 6000-6999)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobResult {
+    /// The kind of result, 1000 more than the request kind
+    pub kind: EventKind,
+
+    /// The job request
+    pub request_id: Id,
+
+    /// A relay where the job request may be found
+    pub request_relay: Option<UncheckedUrl>,
+
+    /// The job request event, as JSON
+    pub request: Option<String>,
+
+    /// The inputs of the job request
+    pub inputs: Vec<JobInput>,
+
+    /// The customer
+    pub customer: PublicKey,
+
+    /// The amount requested for the job
+    pub amount: Option<MilliSatoshi>,
+
+    /// A lightning invoice for the amount
+    pub bolt11: Option<String>,
+
+    /// The result
+    pub content: String,
+}
+
+impl JobResult {
+    /// Create a result for a job request event
+    pub fn new(request: &Event, content: String) -> Result<JobResult, Error> {
+        if !request.kind.is_job_request() {
+            return Err(Error::WrongEventKind);
+        }
+        let kind: u32 = request.kind.into();
+        let inputs = if request.tags.iter().any(|t| t.tagname() == "encrypted") {
+            vec![]
+        } else {
+            JobRequest::from_event(request)?.inputs
+        };
+        Ok(JobResult {
+            kind: EventKind::from(kind + 1000),
+            request_id: request.id,
+            request_relay: None,
+            request: Some(serde_json::to_string(request)?),
+            inputs,
+            customer: request.pubkey,
+            amount: None,
+            bolt11: None,
+            content,
+        })
+    }
+
+    /// Parse a job result event
+    pub fn from_event(event: &Event) -> Result<JobResult, Error> {
+        if !event.kind.is_job_result() {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut request_id: Option<(Id, Option<UncheckedUrl>)> = None;
+        let mut request: Option<String> = None;
+        let mut inputs: Vec<JobInput> = Vec::new();
+        let mut customer: Option<PublicKey> = None;
+        let mut amount: Option<(MilliSatoshi, Option<String>)> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "request" => request = Some(tag.value().to_owned()),
+                "i" => inputs.extend(JobInput::from_tag(tag)),
+                "amount" => amount = Some(read_amount(tag)?),
+                _ => match tag.parse() {
+                    Ok(ParsedTag::Event {
+                        id,
+                        recommended_relay_url,
+                        ..
+                    }) if request_id.is_none() => request_id = Some((id, recommended_relay_url)),
+                    Ok(ParsedTag::Pubkey { pubkey, .. }) if customer.is_none() => {
+                        customer = Some(pubkey)
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        let (request_id, request_relay) =
+            request_id.ok_or_else(|| Error::Dvm("Missing request 'e' tag".to_owned()))?;
+        let (amount, bolt11) = amount.unzip();
+        Ok(JobResult {
+            kind: event.kind,
+            request_id,
+            request_relay,
+            request,
+            inputs,
+            customer: customer.ok_or_else(|| Error::Dvm("Missing customer 'p' tag".to_owned()))?,
+            amount,
+            bolt11: bolt11.flatten(),
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the job result PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(request) = &self.request {
+            tags.push(Tag::new(&["request", request]));
+        }
+        tags.push(request_tag(self.request_id, &self.request_relay));
+        for input in self.inputs.iter() {
+            tags.push(input.to_tag());
+        }
+        tags.push(Tag::new(&["p", &self.customer.as_hex_string()]));
+        if let Some(amount) = self.amount {
+            tags.push(amount_tag(amount, &self.bolt11));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.kind,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// The status of a job, in job feedback
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JobStatus {
+    /// The service provider wants to be paid before doing the job
+    PaymentRequired,
+
+    /// The job is being done
+    Processing,
+
+    /// The job failed
+    Error,
+
+    /// The job is done
+    Success,
+
+    /// Part of the result is available
+    Partial,
+}
+
+impl JobStatus {
+    /// The 'status' tag value
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            JobStatus::PaymentRequired => "payment-required",
+            JobStatus::Processing => "processing",
+            JobStatus::Error => "error",
+            JobStatus::Success => "success",
+            JobStatus::Partial => "partial",
+        }
+    }
+
+    /// Parse a 'status' tag value
+    pub fn from_str_opt(s: &str) -> Option<JobStatus> {
+        match s {
+            "payment-required" => Some(JobStatus::PaymentRequired),
+            "processing" => Some(JobStatus::Processing),
+            "error" => Some(JobStatus::Error),
+            "success" => Some(JobStatus::Success),
+            "partial" => Some(JobStatus::Partial),
+            _ => None,
+        }
+    }
+}
+
+/// NIP-90 job feedback (kind 7000)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JobFeedback {
+    /// The job request
+    pub request_id: Id,
+
+    /// A relay where the job request may be found
+    pub request_relay: Option<UncheckedUrl>,
+
+    /// The customer
+    pub customer: PublicKey,
+
+    /// The status
+    pub status: JobStatus,
+
+    /// More about the status, e.g. an error message
+    pub extra_info: Option<String>,
+
+    /// The amount requested
+    pub amount: Option<MilliSatoshi>,
+
+    /// A lightning invoice for the amount
+    pub bolt11: Option<String>,
+
+    /// Usually empty, or a partial result
+    pub content: String,
+}
+
+impl JobFeedback {
+    /// Create feedback on a job request event
+    pub fn new(request: &Event, status: JobStatus) -> JobFeedback {
+        JobFeedback {
+            request_id: request.id,
+            request_relay: None,
+            customer: request.pubkey,
+            status,
+            extra_info: None,
+            amount: None,
+            bolt11: None,
+            content: "".to_owned(),
+        }
+    }
+
+    /// Parse a kind-7000 JobFeedback event
+    pub fn from_event(event: &Event) -> Result<JobFeedback, Error> {
+        if event.kind != EventKind::JobFeedback {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut request_id: Option<(Id, Option<UncheckedUrl>)> = None;
+        let mut customer: Option<PublicKey> = None;
+        let mut status: Option<(JobStatus, Option<String>)> = None;
+        let mut amount: Option<(MilliSatoshi, Option<String>)> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "status" => {
+                    status = JobStatus::from_str_opt(tag.value())
+                        .map(|s| (s, tag.get_opt_index(2).map(|s| s.to_owned())))
+                }
+                "amount" => amount = Some(read_amount(tag)?),
+                _ => match tag.parse() {
+                    Ok(ParsedTag::Event {
+                        id,
+                        recommended_relay_url,
+                        ..
+                    }) if request_id.is_none() => request_id = Some((id, recommended_relay_url)),
+                    Ok(ParsedTag::Pubkey { pubkey, .. }) if customer.is_none() => {
+                        customer = Some(pubkey)
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        let (request_id, request_relay) =
+            request_id.ok_or_else(|| Error::Dvm("Missing request 'e' tag".to_owned()))?;
+        let (status, extra_info) =
+            status.ok_or_else(|| Error::Dvm("Missing or unknown 'status' tag".to_owned()))?;
+        let (amount, bolt11) = amount.unzip();
+        Ok(JobFeedback {
+            request_id,
+            request_relay,
+            customer: customer.ok_or_else(|| Error::Dvm("Missing customer 'p' tag".to_owned()))?,
+            status,
+            extra_info,
+            amount,
+            bolt11: bolt11.flatten(),
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the kind-7000 JobFeedback PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut status = Tag::new(&["status", self.status.as_str()]);
+        if let Some(extra_info) = &self.extra_info {
+            status.set_index(2, extra_info.clone());
+        }
+        let mut tags = vec![
+            status,
+            request_tag(self.request_id, &self.request_relay),
+            Tag::new(&["p", &self.customer.as_hex_string()]),
+        ];
+        if let Some(amount) = self.amount {
+            tags.push(amount_tag(amount, &self.bolt11));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::JobFeedback,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+fn request_tag(id: Id, relay: &Option<UncheckedUrl>) -> Tag {
+    ParsedTag::Event {
+        id,
+        recommended_relay_url: relay.clone(),
+        marker: None,
+        author_pubkey: None,
+    }
+    .into_tag()
+}
+
+fn read_amount(tag: &Tag) -> Result<(MilliSatoshi, Option<String>), Error> {
+    let amount = tag
+        .value()
+        .parse::<u64>()
+        .map_err(|e| Error::Dvm(format!("Invalid amount '{}': {}", tag.value(), e)))?;
+    Ok((
+        MilliSatoshi(amount),
+        tag.get_opt_index(2).map(|s| s.to_owned()),
+    ))
+}
+
+fn amount_tag(amount: MilliSatoshi, bolt11: &Option<String>) -> Tag {
+    let mut tag = Tag::new(&["amount", &amount.0.to_string()]);
+    if let Some(bolt11) = bolt11 {
+        tag.set_index(2, bolt11.clone());
+    }
+    tag
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PrivateKey;
+
+    #[test]
+    fn test_job_request() {
+        let customer = PrivateKey::generate();
+        let provider = PrivateKey::generate();
+
+        let mut request = JobRequest::new(EventKind::JobRequest(5001)).unwrap();
+        request
+            .inputs
+            .push(JobInput::new("Some text".to_owned(), JobInputType::Text));
+        let mut input = JobInput::new(Id::mock().as_hex_string(), JobInputType::Event);
+        input.relay = Some(UncheckedUrl("wss://relay.example.com".to_owned()));
+        input.marker = Some("source".to_owned());
+        request.inputs.push(input);
+        request.output = Some("text/plain".to_owned());
+        request.params.push(("lang".to_owned(), "es".to_owned()));
+        request.bid = Some(MilliSatoshi(5000));
+
+        let event = customer
+            .sign_event(request.to_pre_event(customer.public_key()))
+            .unwrap();
+        assert_eq!(JobRequest::from_event(&event).unwrap(), request);
+        assert!(JobRequest::new(EventKind::TextNote).is_err());
+
+        // A malformed bid does not spoil the request
+        let mut pre_event = request.to_pre_event(customer.public_key());
+        for tag in pre_event.tags.iter_mut() {
+            if tag.tagname() == "bid" {
+                tag.set_index(1, "lots".to_owned());
+            }
+        }
+        let event = customer.sign_event(pre_event).unwrap();
+        let received = JobRequest::from_event(&event).unwrap();
+        assert_eq!(received.bid, None);
+        assert_eq!(received.inputs, request.inputs);
+
+        // Encrypted to the provider
+        let event = customer
+            .sign_event(
+                request
+                    .to_encrypted_pre_event(&customer, provider.public_key())
+                    .unwrap(),
+            )
+            .unwrap();
+        let mut received = JobRequest::from_event(&event).unwrap();
+        assert!(received.is_encrypted());
+        assert!(received.inputs.is_empty());
+        received.decrypt(&provider, &customer.public_key()).unwrap();
+        assert!(!received.is_encrypted());
+        assert_eq!(received.inputs, request.inputs);
+        assert_eq!(received.params, request.params);
+        assert_eq!(received.service_providers, vec![provider.public_key()]);
+    }
+
+    #[test]
+    fn test_job_result_feedback_and_chaining() {
+        let customer = PrivateKey::generate();
+        let provider = PrivateKey::generate();
+
+        let mut first = JobRequest::new(EventKind::JobRequest(5000)).unwrap();
+        first.inputs.push(JobInput::new(
+            "https://example.com/a.mp3".to_owned(),
+            JobInputType::Url,
+        ));
+        let first_event = customer
+            .sign_event(first.to_pre_event(customer.public_key()))
+            .unwrap();
+
+        let mut feedback = JobFeedback::new(&first_event, JobStatus::PaymentRequired);
+        feedback.amount = Some(MilliSatoshi(1000));
+        feedback.bolt11 = Some("lnbc10n1...".to_owned());
+        let feedback_event = provider
+            .sign_event(feedback.to_pre_event(provider.public_key()))
+            .unwrap();
+        assert_eq!(JobFeedback::from_event(&feedback_event).unwrap(), feedback);
+
+        let result = JobResult::new(&first_event, "A transcript".to_owned()).unwrap();
+        assert_eq!(result.kind, EventKind::JobResult(6000));
+        let result_event = provider
+            .sign_event(result.to_pre_event(provider.public_key()))
+            .unwrap();
+        assert_eq!(JobResult::from_event(&result_event).unwrap(), result);
+
+        // A second job using the first job's result
+        let mut second = JobRequest::new(EventKind::JobRequest(5001)).unwrap();
+        second.inputs.push(JobInput::new(
+            first_event.id.as_hex_string(),
+            JobInputType::Job,
+        ));
+        assert!(second.resolve_inputs(&[]).is_err());
+        let resolved = second
+            .resolve_inputs(&[feedback_event, result_event])
+            .unwrap();
+        assert_eq!(
+            resolved,
+            vec![JobInput::new("A transcript".to_owned(), JobInputType::Text)]
+        );
+    }
+}
@@ -185,6 +185,10 @@ define_event_kinds!(
     "Wiki Merge Requests",
     WikiMergeRequest = 818,
 
+    "Poll Response (NIP-88)",
+    "Poll Response",
+    PollResponse = 1018,
+
     "Bid (NIP-15)",
     "Bid",
     Bid = 1021,
@@ -205,6 +209,10 @@ define_event_kinds!(
     "File Metadata",
     FileMetadata = 1063,
 
+    "Poll (NIP-88)",
+    "Poll",
+    Poll = 1068,
+
     "Comment (NIP-22 PR #1233)" ,
     "Comment",
     Comment = 1111,
@@ -377,6 +385,14 @@ define_event_kinds!(
     "Wallet Response",
     WalletResponse = 23195,
 
+    "Wallet Notification, NIP-04 encrypted (NIP-47)",
+    "Wallet Notification (NIP-04)",
+    WalletNotificationNip04 = 23196,
+
+    "Wallet Notification (NIP-47)",
+    "Wallet Notification",
+    WalletNotification = 23197,
+
     "Nostr Connect (NIP-46)",
     "Nostr Connect",
     NostrConnect = 24133,
@@ -627,6 +643,7 @@ impl EventKind {
                 | Picture
                 | ChannelMessage
                 | FileMetadata
+                | Poll
                 | Comment
                 | LiveChatMessage
                 | Patches
@@ -697,6 +714,8 @@ impl EventKind {
                 | JobResult(_)
                 | WalletRequest
                 | WalletResponse
+                | WalletNotificationNip04
+                | WalletNotification
                 | NostrConnect
         )
     }
@@ -71,6 +71,18 @@ impl FileMetadata {
         }
     }
 
+    /// Create a FileMetadata for local file contents, filling in the sha256 hash,
+    /// the size, and the mime type if it can be recognized from the leading bytes
+    pub fn from_bytes(url: UncheckedUrl, bytes: &[u8]) -> FileMetadata {
+        use sha2::{Digest, Sha256};
+
+        let mut fm = FileMetadata::new(url);
+        fm.x = Some(hex::encode(Sha256::digest(bytes)));
+        fm.size = Some(bytes.len() as u64);
+        fm.m = sniff_mime_type(bytes).map(|m| m.to_owned());
+        fm
+    }
+
     /// Create a NIP-94 FileMetadata PreEvent from this FileMetadata
     pub fn to_nip94_preevent(&self, pubkey: PublicKey) -> PreEvent {
         let mut tags = vec![Tag::new(&["url", &self.url.0])];
@@ -146,9 +158,15 @@ impl FileMetadata {
             return None;
         }
 
+        FileMetadata::from_nip94_tags(&event.tags)
+    }
+
+    /// Create a FileMetadata from the tags of a NIP-94 event, such as those
+    /// embedded in server responses. Returns None if there is no 'url' tag.
+    pub fn from_nip94_tags(tags: &[Tag]) -> Option<FileMetadata> {
         let mut fm = FileMetadata::new(UncheckedUrl("".to_owned()));
 
-        for tag in &event.tags {
+        for tag in tags {
             match tag.tagname() {
                 "url" => fm.url = UncheckedUrl(tag.value().to_owned()),
                 "m" => fm.m = Some(tag.value().to_owned()),
@@ -304,6 +322,50 @@ impl FileMetadata {
     }
 }
 
+// Recognize common file types by their magic numbers
+fn sniff_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"OggS", "audio/ogg"),
+        (b"fLaC", "audio/flac"),
+        (b"ID3", "audio/mpeg"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"PK\x03\x04", "application/zip"),
+    ];
+
+    for (magic, mime) in SIGNATURES.iter() {
+        if bytes.starts_with(magic) {
+            return Some(mime);
+        }
+    }
+
+    if bytes.len() >= 12 {
+        if bytes.starts_with(b"RIFF") {
+            match &bytes[8..12] {
+                b"WEBP" => return Some("image/webp"),
+                b"WAVE" => return Some("audio/wav"),
+                b"AVI " => return Some("video/x-msvideo"),
+                _ => {}
+            }
+        }
+        if &bytes[4..8] == b"ftyp" {
+            return match &bytes[8..12] {
+                b"avif" => Some("image/avif"),
+                b"heic" | b"heix" => Some("image/heic"),
+                b"qt  " => Some("video/quicktime"),
+                b"M4A " => Some("audio/mp4"),
+                _ => Some("video/mp4"),
+            };
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -339,4 +401,18 @@ mod test {
         let fm2 = FileMetadata::from_imeta_tag(&tag).unwrap();
         assert_eq!(fm, fm2);
     }
+
+    #[test]
+    fn test_from_bytes() {
+        let bytes = b"\x89PNG\r\n\x1a\nnot really a png";
+        let fm = FileMetadata::from_bytes(UncheckedUrl("".to_owned()), bytes);
+        assert_eq!(fm.m.as_deref(), Some("image/png"));
+        assert_eq!(fm.size, Some(24));
+        assert_eq!(
+            FileMetadata::from_bytes(UncheckedUrl("".to_owned()), b"")
+                .x
+                .as_deref(),
+            Some("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
 }
@@ -0,0 +1,705 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+use std::collections::{BTreeMap, HashMap};
+
+/// A NIP-34 git repository announcement (kind 30617)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitRepository {
+    /// The repository identifier (the 'd' tag), usually kebab-case
+    pub id: String,
+
+    /// A human readable name
+    pub name: Option<String>,
+
+    /// A description
+    pub description: Option<String>,
+
+    /// URLs to browse the repository
+    pub web: Vec<UncheckedUrl>,
+
+    /// URLs to clone the repository from
+    pub clone: Vec<UncheckedUrl>,
+
+    /// Relays where patches and issues should be sent
+    pub relays: Vec<UncheckedUrl>,
+
+    /// The earliest commit unique to this repository, identifying it among forks
+    pub earliest_unique_commit: Option<String>,
+
+    /// Other people who maintain the repository
+    pub maintainers: Vec<PublicKey>,
+
+    /// Hashtags
+    pub hashtags: Vec<String>,
+}
+
+impl GitRepository {
+    /// Create a new repository announcement
+    pub fn new(id: String) -> GitRepository {
+        GitRepository {
+            id,
+            ..Default::default()
+        }
+    }
+
+    /// The address of this repository, as announced by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.id.clone(),
+            relays: vec![],
+            kind: EventKind::RepositoryAnnouncement,
+            author,
+        }
+    }
+
+    /// Everyone who may set the status of patches and issues: the author of the
+    /// announcement and the listed maintainers
+    pub fn all_maintainers(&self, author: PublicKey) -> Vec<PublicKey> {
+        let mut output = vec![author];
+        for pubkey in self.maintainers.iter() {
+            if !output.contains(pubkey) {
+                output.push(*pubkey);
+            }
+        }
+        output
+    }
+
+    /// Parse a kind-30617 RepositoryAnnouncement event
+    pub fn from_event(event: &Event) -> Result<GitRepository, Error> {
+        if event.kind != EventKind::RepositoryAnnouncement {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut repo = GitRepository::default();
+        for tag in event.tags.iter() {
+            let values = (1..tag.len()).map(|i| tag.get_index(i));
+            match tag.tagname() {
+                "d" => repo.id = tag.value().to_owned(),
+                "name" => repo.name = Some(tag.value().to_owned()),
+                "description" => repo.description = Some(tag.value().to_owned()),
+                "web" => repo.web.extend(values.map(|v| UncheckedUrl(v.to_owned()))),
+                "clone" => repo
+                    .clone
+                    .extend(values.map(|v| UncheckedUrl(v.to_owned()))),
+                "relays" => repo
+                    .relays
+                    .extend(values.map(|v| UncheckedUrl(v.to_owned()))),
+                "r" if tag.get_opt_index(2) == Some("euc") => {
+                    repo.earliest_unique_commit = Some(tag.value().to_owned())
+                }
+                "maintainers" => repo
+                    .maintainers
+                    .extend(values.filter_map(|v| PublicKey::try_from_hex_string(v, true).ok())),
+                "t" => repo.hashtags.push(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+        Ok(repo)
+    }
+
+    /// Create the kind-30617 RepositoryAnnouncement PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.id])];
+        if let Some(name) = &self.name {
+            tags.push(Tag::new(&["name", name]));
+        }
+        if let Some(description) = &self.description {
+            tags.push(Tag::new(&["description", description]));
+        }
+        let lists = [
+            ("web", &self.web),
+            ("clone", &self.clone),
+            ("relays", &self.relays),
+        ];
+        for (tagname, urls) in lists.iter() {
+            if !urls.is_empty() {
+                let mut tag = Tag::new(&[tagname]);
+                tag.push_values(urls.iter().map(|u| u.0.clone()).collect());
+                tags.push(tag);
+            }
+        }
+        if let Some(commit) = &self.earliest_unique_commit {
+            tags.push(Tag::new(&["r", commit, "euc"]));
+        }
+        if !self.maintainers.is_empty() {
+            let mut tag = Tag::new(&["maintainers"]);
+            tag.push_values(
+                self.maintainers
+                    .iter()
+                    .map(|pk| pk.as_hex_string())
+                    .collect(),
+            );
+            tags.push(tag);
+        }
+        for hashtag in self.hashtags.iter() {
+            tags.push(Tag::new(&["t", hashtag]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::RepositoryAnnouncement,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+}
+
+/// A NIP-34 git repository state announcement (kind 30618): where the branches
+/// and tags point
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitRepositoryState {
+    /// The repository identifier (the 'd' tag)
+    pub id: String,
+
+    /// The ref HEAD points at, e.g. "refs/heads/master"
+    pub head: Option<String>,
+
+    /// Refs (e.g. "refs/heads/master", "refs/tags/v1.0") to commit ids
+    pub refs: BTreeMap<String, String>,
+}
+
+impl GitRepositoryState {
+    /// Parse a kind-30618 RepositoryStateAnnouncement event
+    pub fn from_event(event: &Event) -> Result<GitRepositoryState, Error> {
+        if event.kind != EventKind::RepositoryStateAnnouncement {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut state = GitRepositoryState::default();
+        for tag in event.tags.iter() {
+            let name = tag.tagname();
+            if name == "d" {
+                state.id = tag.value().to_owned();
+            } else if name == "HEAD" {
+                state.head = tag.value().strip_prefix("ref: ").map(|r| r.to_owned());
+            } else if name.starts_with("refs/") && !tag.value().is_empty() {
+                let _ = state.refs.insert(name.to_owned(), tag.value().to_owned());
+            }
+        }
+        Ok(state)
+    }
+
+    /// Create the kind-30618 RepositoryStateAnnouncement PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.id])];
+        for (name, commit) in self.refs.iter() {
+            tags.push(Tag::new(&[name, commit]));
+        }
+        if let Some(head) = &self.head {
+            tags.push(Tag::new(&["HEAD", &format!("ref: {head}")]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::RepositoryStateAnnouncement,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+
+    /// Branch names to commit ids
+    pub fn branches(&self) -> BTreeMap<&str, &str> {
+        self.refs_under("refs/heads/")
+    }
+
+    /// Tag names to commit ids
+    pub fn tags(&self) -> BTreeMap<&str, &str> {
+        self.refs_under("refs/tags/")
+    }
+
+    fn refs_under(&self, prefix: &str) -> BTreeMap<&str, &str> {
+        self.refs
+            .iter()
+            .filter_map(|(name, commit)| Some((name.strip_prefix(prefix)?, commit.as_str())))
+            .collect()
+    }
+}
+
+/// The committer of a patch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitCommitter {
+    /// Their name
+    pub name: String,
+
+    /// Their email address
+    pub email: String,
+
+    /// When they committed
+    pub timestamp: Unixtime,
+
+    /// Their timezone offset in minutes
+    pub timezone_offset: i32,
+}
+
+/// A NIP-34 git patch (kind 1617)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GitPatch {
+    /// The repository the patch is for
+    pub repository: Option<NAddr>,
+
+    /// The earliest unique commit of the repository
+    pub earliest_unique_commit: Option<String>,
+
+    /// Whether this is the first patch in a series
+    pub is_root: bool,
+
+    /// Whether this is the first patch in a revision of an earlier series
+    pub is_root_revision: bool,
+
+    /// The patch this one follows, or for a root revision, the original root
+    pub in_reply_to: Option<Id>,
+
+    /// The commit id this patch creates
+    pub commit: Option<String>,
+
+    /// The commit id this patch applies on top of
+    pub parent_commit: Option<String>,
+
+    /// The committer, if given and well formed
+    pub committer: Option<GitCommitter>,
+
+    /// The output of `git format-patch`
+    pub content: String,
+}
+
+impl GitPatch {
+    /// Parse a kind-1617 Patches event
+    pub fn from_event(event: &Event) -> Result<GitPatch, Error> {
+        if event.kind != EventKind::Patches {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut patch = GitPatch {
+            content: event.content.clone(),
+            ..Default::default()
+        };
+        let mut references: Vec<&str> = Vec::new();
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "t" if tag.value() == "root" => patch.is_root = true,
+                "t" if tag.value() == "root-revision" => patch.is_root_revision = true,
+                "r" => references.push(tag.value()),
+                "commit" => patch.commit = Some(tag.value().to_owned()),
+                "parent-commit" => patch.parent_commit = Some(tag.value().to_owned()),
+                "committer" => {
+                    // A malformed committer is dropped rather than failing the patch
+                    patch.committer = match (
+                        tag.get_index(3).parse::<i64>(),
+                        tag.get_index(4).parse::<i32>(),
+                    ) {
+                        (Ok(timestamp), Ok(timezone_offset)) => Some(GitCommitter {
+                            name: tag.value().to_owned(),
+                            email: tag.get_index(2).to_owned(),
+                            timestamp: Unixtime(timestamp),
+                            timezone_offset,
+                        }),
+                        _ => None,
+                    }
+                }
+                _ => match tag.parse() {
+                    Ok(ParsedTag::Address { address, .. })
+                        if address.kind == EventKind::RepositoryAnnouncement =>
+                    {
+                        patch.repository = Some(address)
+                    }
+                    Ok(ParsedTag::Event { id, marker, .. })
+                        if marker.as_deref() == Some("reply") =>
+                    {
+                        patch.in_reply_to = Some(id)
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        // The commit is also an 'r' tag, so that patches can be found by commit
+        patch.earliest_unique_commit = references
+            .into_iter()
+            .find(|r| Some(*r) != patch.commit.as_deref())
+            .map(|r| r.to_owned());
+        Ok(patch)
+    }
+
+    /// Create the kind-1617 Patches PreEvent. `maintainers` are tagged so they
+    /// are notified.
+    pub fn to_pre_event(&self, pubkey: PublicKey, maintainers: &[PublicKey]) -> PreEvent {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(repository) = &self.repository {
+            tags.push(
+                ParsedTag::Address {
+                    address: repository.clone(),
+                    marker: None,
+                }
+                .into_tag(),
+            );
+        }
+        if let Some(commit) = &self.earliest_unique_commit {
+            tags.push(Tag::new(&["r", commit]));
+        }
+        for maintainer in maintainers.iter() {
+            tags.push(Tag::new(&["p", &maintainer.as_hex_string()]));
+        }
+        if self.is_root {
+            tags.push(Tag::new(&["t", "root"]));
+        }
+        if self.is_root_revision {
+            tags.push(Tag::new(&["t", "root-revision"]));
+        }
+        if let Some(id) = self.in_reply_to {
+            tags.push(event_tag(id, "reply"));
+        }
+        if let Some(commit) = &self.commit {
+            tags.push(Tag::new(&["commit", commit]));
+            tags.push(Tag::new(&["r", commit]));
+        }
+        if let Some(parent) = &self.parent_commit {
+            tags.push(Tag::new(&["parent-commit", parent]));
+        }
+        if let Some(c) = &self.committer {
+            tags.push(Tag::new(&[
+                "committer",
+                &c.name,
+                &c.email,
+                &c.timestamp.0.to_string(),
+                &c.timezone_offset.to_string(),
+            ]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::Patches,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// The status of a patch or issue
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GitStatus {
+    /// Open
+    Open,
+
+    /// Applied or merged (for patches), or resolved (for issues)
+    Applied,
+
+    /// Closed
+    Closed,
+
+    /// A draft
+    Draft,
+}
+
+impl GitStatus {
+    /// The status event kind
+    pub fn kind(&self) -> EventKind {
+        match *self {
+            GitStatus::Open => EventKind::GitStatusOpen,
+            GitStatus::Applied => EventKind::GitStatusApplied,
+            GitStatus::Closed => EventKind::GitStatusClosed,
+            GitStatus::Draft => EventKind::GitStatusDraft,
+        }
+    }
+
+    /// The status of a status event kind
+    pub fn from_kind(kind: EventKind) -> Option<GitStatus> {
+        match kind {
+            EventKind::GitStatusOpen => Some(GitStatus::Open),
+            EventKind::GitStatusApplied => Some(GitStatus::Applied),
+            EventKind::GitStatusClosed => Some(GitStatus::Closed),
+            EventKind::GitStatusDraft => Some(GitStatus::Draft),
+            _ => None,
+        }
+    }
+}
+
+/// A NIP-34 status event (kinds 1630-1633) setting the status of a patch or issue
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitStatusUpdate {
+    /// The new status
+    pub status: GitStatus,
+
+    /// The patch (root patch of a series) or issue
+    pub target: Id,
+
+    /// The revision this applies to, if it is a patch revision
+    pub revision: Option<Id>,
+
+    /// The repository
+    pub repository: Option<NAddr>,
+
+    /// For applied patches, the merge commit
+    pub merge_commit: Option<String>,
+
+    /// For applied patches, the commits they were applied as
+    pub applied_as_commits: Vec<String>,
+
+    /// A comment
+    pub content: String,
+}
+
+impl GitStatusUpdate {
+    /// Create a new status update
+    pub fn new(status: GitStatus, target: Id) -> GitStatusUpdate {
+        GitStatusUpdate {
+            status,
+            target,
+            revision: None,
+            repository: None,
+            merge_commit: None,
+            applied_as_commits: vec![],
+            content: "".to_owned(),
+        }
+    }
+
+    /// Parse a status event
+    pub fn from_event(event: &Event) -> Result<GitStatusUpdate, Error> {
+        let status = GitStatus::from_kind(event.kind).ok_or(Error::WrongEventKind)?;
+
+        let mut target: Option<Id> = None;
+        let mut revision: Option<Id> = None;
+        let mut repository: Option<NAddr> = None;
+        let mut merge_commit: Option<String> = None;
+        let mut applied_as_commits: Vec<String> = Vec::new();
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "merge-commit" => merge_commit = Some(tag.value().to_owned()),
+                "applied-as-commits" => {
+                    for i in 1..tag.len() {
+                        applied_as_commits.push(tag.get_index(i).to_owned());
+                    }
+                }
+                _ => match tag.parse() {
+                    Ok(ParsedTag::Event { id, marker, .. }) => match marker.as_deref() {
+                        Some("root") => target = Some(id),
+                        Some("reply") => revision = Some(id),
+                        _ => {}
+                    },
+                    Ok(ParsedTag::Address { address, .. })
+                        if address.kind == EventKind::RepositoryAnnouncement =>
+                    {
+                        repository = Some(address)
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        Ok(GitStatusUpdate {
+            status,
+            target: target.ok_or_else(|| Error::Git("Missing 'root' e tag".to_owned()))?,
+            revision,
+            repository,
+            merge_commit,
+            applied_as_commits,
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the status PreEvent. The author of the patch or issue, and the
+    /// maintainers, should be tagged in `notify`.
+    pub fn to_pre_event(&self, pubkey: PublicKey, notify: &[PublicKey]) -> PreEvent {
+        let mut tags = vec![event_tag(self.target, "root")];
+        if let Some(revision) = self.revision {
+            tags.push(event_tag(revision, "reply"));
+        }
+        for pk in notify.iter() {
+            tags.push(Tag::new(&["p", &pk.as_hex_string()]));
+        }
+        if let Some(repository) = &self.repository {
+            tags.push(
+                ParsedTag::Address {
+                    address: repository.clone(),
+                    marker: None,
+                }
+                .into_tag(),
+            );
+        }
+        if let Some(commit) = &self.merge_commit {
+            tags.push(Tag::new(&["merge-commit", commit]));
+        }
+        if !self.applied_as_commits.is_empty() {
+            let mut tag = Tag::new(&["applied-as-commits"]);
+            tag.push_values(self.applied_as_commits.clone());
+            tags.push(tag);
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.status.kind(),
+            tags,
+            content: self.content.clone(),
+        }
+    }
+
+    /// The current status of each patch or issue in `targets`.
+    ///
+    /// Only status events from a target's author or from `maintainers` count, and
+    /// the latest of those wins. Targets with no such status event are open.
+    pub fn current_statuses(
+        targets: &[Event],
+        maintainers: &[PublicKey],
+        statuses: &[Event],
+    ) -> HashMap<Id, GitStatus> {
+        let authors: HashMap<Id, PublicKey> = targets.iter().map(|e| (e.id, e.pubkey)).collect();
+
+        let mut latest: HashMap<Id, (Unixtime, GitStatus)> = HashMap::new();
+        for event in statuses.iter() {
+            let update = match GitStatusUpdate::from_event(event) {
+                Ok(u) => u,
+                Err(_) => continue,
+            };
+            let author = match authors.get(&update.target) {
+                Some(pk) => pk,
+                None => continue,
+            };
+            if event.pubkey != *author && !maintainers.contains(&event.pubkey) {
+                continue;
+            }
+            match latest.get(&update.target) {
+                Some((at, _)) if *at >= event.created_at => {}
+                _ => {
+                    let _ = latest.insert(update.target, (event.created_at, update.status));
+                }
+            }
+        }
+
+        targets
+            .iter()
+            .map(|e| {
+                let status = latest.get(&e.id).map(|(_, s)| *s);
+                (e.id, status.unwrap_or(GitStatus::Open))
+            })
+            .collect()
+    }
+}
+
+fn event_tag(id: Id, marker: &str) -> Tag {
+    ParsedTag::Event {
+        id,
+        recommended_relay_url: None,
+        marker: Some(marker.to_owned()),
+        author_pubkey: None,
+    }
+    .into_tag()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_git_repository() {
+        let private_key = PrivateKey::generate();
+        let mut repo = GitRepository::new("nostr-types".to_owned());
+        repo.name = Some("nostr-types".to_owned());
+        repo.clone.push(UncheckedUrl(
+            "https://github.com/mikedilger/nostr-types".to_owned(),
+        ));
+        repo.relays
+            .push(UncheckedUrl("wss://relay.example.com".to_owned()));
+        repo.earliest_unique_commit = Some("8d8e3d1b".to_owned());
+        repo.maintainers.push(PublicKey::mock());
+        let event = private_key
+            .sign_event(repo.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(GitRepository::from_event(&event).unwrap(), repo);
+
+        let mut state = GitRepositoryState {
+            id: "nostr-types".to_owned(),
+            head: Some("refs/heads/master".to_owned()),
+            refs: BTreeMap::new(),
+        };
+        let _ = state
+            .refs
+            .insert("refs/heads/master".to_owned(), "abc123".to_owned());
+        let _ = state
+            .refs
+            .insert("refs/tags/v1.0".to_owned(), "def456".to_owned());
+        let event = private_key
+            .sign_event(state.to_pre_event(private_key.public_key()))
+            .unwrap();
+        let parsed = GitRepositoryState::from_event(&event).unwrap();
+        assert_eq!(parsed, state);
+        assert_eq!(parsed.branches().get("master"), Some(&"abc123"));
+        assert_eq!(parsed.tags().get("v1.0"), Some(&"def456"));
+    }
+
+    #[test]
+    fn test_git_patch_status() {
+        let owner = PrivateKey::generate();
+        let maintainer = PrivateKey::generate();
+        let contributor = PrivateKey::generate();
+        let stranger = PrivateKey::generate();
+
+        let repo = GitRepository {
+            maintainers: vec![maintainer.public_key()],
+            ..GitRepository::new("project".to_owned())
+        };
+        let maintainers = repo.all_maintainers(owner.public_key());
+
+        let patch = GitPatch {
+            repository: Some(repo.address(owner.public_key())),
+            is_root: true,
+            commit: Some("b1b2".to_owned()),
+            parent_commit: Some("a1a2".to_owned()),
+            committer: Some(GitCommitter {
+                name: "Some One".to_owned(),
+                email: "someone@example.com".to_owned(),
+                timestamp: Unixtime(1_700_000_000),
+                timezone_offset: -300,
+            }),
+            earliest_unique_commit: Some("0a0b".to_owned()),
+            content: "From b1b2 ...".to_owned(),
+            ..Default::default()
+        };
+        let patch_event = contributor
+            .sign_event(patch.to_pre_event(contributor.public_key(), &maintainers))
+            .unwrap();
+        assert_eq!(GitPatch::from_event(&patch_event).unwrap(), patch);
+
+        // A committer without a timestamp is dropped
+        let mut pre_event = patch.to_pre_event(contributor.public_key(), &maintainers);
+        for tag in pre_event.tags.iter_mut() {
+            if tag.tagname() == "committer" {
+                tag.set_index(3, "".to_owned());
+            }
+        }
+        let malformed_event = contributor.sign_event(pre_event).unwrap();
+        let malformed = GitPatch::from_event(&malformed_event).unwrap();
+        assert_eq!(malformed.committer, None);
+        assert_eq!(malformed.commit, patch.commit);
+
+        let status = |key: &PrivateKey, status: GitStatus, at: i64| {
+            let mut pre_event = GitStatusUpdate::new(status, patch_event.id)
+                .to_pre_event(key.public_key(), &[contributor.public_key()]);
+            pre_event.created_at = Unixtime(at);
+            key.sign_event(pre_event).unwrap()
+        };
+        let statuses = vec![
+            status(&contributor, GitStatus::Draft, 100),
+            status(&maintainer, GitStatus::Applied, 200),
+            status(&stranger, GitStatus::Closed, 300),
+        ];
+        let current = GitStatusUpdate::current_statuses(
+            std::slice::from_ref(&patch_event),
+            &maintainers,
+            &statuses,
+        );
+        assert_eq!(current[&patch_event.id], GitStatus::Applied);
+
+        let current = GitStatusUpdate::current_statuses(
+            std::slice::from_ref(&patch_event),
+            &maintainers,
+            &[],
+        );
+        assert_eq!(current[&patch_event.id], GitStatus::Open);
+    }
+}
@@ -0,0 +1,628 @@
+use crate::types::{Event, EventKind, Id, ParsedTag, PreEvent, PublicKey, Tag, Unixtime};
+use crate::Error;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// NIP-29 group metadata, from a kind-39000 event signed by the relay
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupMetadata {
+    /// The group id
+    pub id: String,
+
+    /// The group name
+    pub name: Option<String>,
+
+    /// The group picture
+    pub picture: Option<String>,
+
+    /// A description of the group
+    pub about: Option<String>,
+
+    /// Whether only members can read the group
+    pub private: bool,
+
+    /// Whether join requests are ignored (joining needs an invite)
+    pub closed: bool,
+}
+
+impl GroupMetadata {
+    /// Parse a kind-39000 group metadata event
+    pub fn from_event(event: &Event) -> Result<GroupMetadata, Error> {
+        if event.kind != EventKind::GroupMetadata(39000) {
+            return Err(Error::WrongEventKind);
+        }
+        let mut metadata = GroupMetadata {
+            id: event.parameter().unwrap_or_default(),
+            ..Default::default()
+        };
+        metadata.read_tags(&event.tags);
+        Ok(metadata)
+    }
+
+    fn read_tags(&mut self, tags: &[Tag]) {
+        for tag in tags.iter() {
+            match tag.tagname() {
+                "name" => self.name = Some(tag.value().to_owned()),
+                "picture" => self.picture = Some(tag.value().to_owned()),
+                "about" => self.about = Some(tag.value().to_owned()),
+                "private" => self.private = true,
+                "public" => self.private = false,
+                "closed" => self.closed = true,
+                "open" => self.closed = false,
+                _ => {}
+            }
+        }
+    }
+
+    fn tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(name) = &self.name {
+            tags.push(Tag::new(&["name", name]));
+        }
+        if let Some(picture) = &self.picture {
+            tags.push(Tag::new(&["picture", picture]));
+        }
+        if let Some(about) = &self.about {
+            tags.push(Tag::new(&["about", about]));
+        }
+        tags.push(Tag::new(&[if self.private { "private" } else { "public" }]));
+        tags.push(Tag::new(&[if self.closed { "closed" } else { "open" }]));
+        tags
+    }
+
+    /// Create the kind-39000 group metadata PreEvent (for the relay to sign)
+    pub fn to_pre_event(&self, relay_pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.id])];
+        tags.extend(self.tags());
+
+        PreEvent {
+            pubkey: relay_pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::GroupMetadata(39000),
+            tags,
+            content: "".to_owned(),
+        }
+    }
+}
+
+/// A change to group metadata, from a kind-9002 moderation event. Only the
+/// fields that are present are changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupMetadataEdit {
+    /// The new group name
+    pub name: Option<String>,
+
+    /// The new group picture
+    pub picture: Option<String>,
+
+    /// The new description of the group
+    pub about: Option<String>,
+
+    /// Whether only members can read the group
+    pub private: Option<bool>,
+
+    /// Whether join requests are ignored (joining needs an invite)
+    pub closed: Option<bool>,
+}
+
+impl GroupMetadataEdit {
+    fn read_tags(&mut self, tags: &[Tag]) {
+        for tag in tags.iter() {
+            match tag.tagname() {
+                "name" => self.name = Some(tag.value().to_owned()),
+                "picture" => self.picture = Some(tag.value().to_owned()),
+                "about" => self.about = Some(tag.value().to_owned()),
+                "private" => self.private = Some(true),
+                "public" => self.private = Some(false),
+                "closed" => self.closed = Some(true),
+                "open" => self.closed = Some(false),
+                _ => {}
+            }
+        }
+    }
+
+    fn tags(&self) -> Vec<Tag> {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(name) = &self.name {
+            tags.push(Tag::new(&["name", name]));
+        }
+        if let Some(picture) = &self.picture {
+            tags.push(Tag::new(&["picture", picture]));
+        }
+        if let Some(about) = &self.about {
+            tags.push(Tag::new(&["about", about]));
+        }
+        if let Some(private) = self.private {
+            tags.push(Tag::new(&[if private { "private" } else { "public" }]));
+        }
+        if let Some(closed) = self.closed {
+            tags.push(Tag::new(&[if closed { "closed" } else { "open" }]));
+        }
+        tags
+    }
+
+    /// Apply the change to `metadata`
+    pub fn apply_to(&self, metadata: &mut GroupMetadata) {
+        if let Some(name) = &self.name {
+            metadata.name = Some(name.clone());
+        }
+        if let Some(picture) = &self.picture {
+            metadata.picture = Some(picture.clone());
+        }
+        if let Some(about) = &self.about {
+            metadata.about = Some(about.clone());
+        }
+        if let Some(private) = self.private {
+            metadata.private = private;
+        }
+        if let Some(closed) = self.closed {
+            metadata.closed = closed;
+        }
+    }
+}
+
+/// The admins, members or roles of a group, from a kind-39001, 39002 or 39003
+/// event signed by the relay
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupList {
+    /// Admins and their roles (kind 39001)
+    Admins(Vec<(PublicKey, Vec<String>)>),
+
+    /// Members (kind 39002)
+    Members(Vec<PublicKey>),
+
+    /// The roles the group supports, with descriptions (kind 39003)
+    Roles(Vec<(String, Option<String>)>),
+}
+
+impl GroupList {
+    /// The event kind
+    pub fn kind(&self) -> EventKind {
+        match self {
+            GroupList::Admins(_) => EventKind::GroupMetadata(39001),
+            GroupList::Members(_) => EventKind::GroupMetadata(39002),
+            GroupList::Roles(_) => EventKind::GroupMetadata(39003),
+        }
+    }
+
+    /// Parse a kind-39001, 39002 or 39003 event, returning the group id and list
+    pub fn from_event(event: &Event) -> Result<(String, GroupList), Error> {
+        let mut list = match event.kind {
+            EventKind::GroupMetadata(39001) => GroupList::Admins(vec![]),
+            EventKind::GroupMetadata(39002) => GroupList::Members(vec![]),
+            EventKind::GroupMetadata(39003) => GroupList::Roles(vec![]),
+            _ => return Err(Error::WrongEventKind),
+        };
+        for tag in event.tags.iter() {
+            match (&mut list, tag.tagname()) {
+                (GroupList::Admins(admins), "p") => {
+                    if let Some((pubkey, roles)) = read_pubkey_and_roles(tag) {
+                        admins.push((pubkey, roles));
+                    }
+                }
+                (GroupList::Members(members), "p") => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        members.push(pubkey);
+                    }
+                }
+                (GroupList::Roles(roles), "role") => roles.push((
+                    tag.value().to_owned(),
+                    tag.get_opt_index(2).map(|s| s.to_owned()),
+                )),
+                _ => {}
+            }
+        }
+        Ok((event.parameter().unwrap_or_default(), list))
+    }
+
+    /// Create the PreEvent (for the relay to sign)
+    pub fn to_pre_event(&self, relay_pubkey: PublicKey, group_id: &str) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", group_id])];
+        match self {
+            GroupList::Admins(admins) => {
+                for (pubkey, roles) in admins.iter() {
+                    tags.push(pubkey_and_roles_tag(pubkey, roles));
+                }
+            }
+            GroupList::Members(members) => {
+                for pubkey in members.iter() {
+                    tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+                }
+            }
+            GroupList::Roles(roles) => {
+                for (name, description) in roles.iter() {
+                    let mut tag = Tag::new(&["role", name]);
+                    if let Some(description) = description {
+                        tag.set_index(2, description.clone());
+                    }
+                    tags.push(tag);
+                }
+            }
+        }
+
+        PreEvent {
+            pubkey: relay_pubkey,
+            created_at: Unixtime::now(),
+            kind: self.kind(),
+            tags,
+            content: "".to_owned(),
+        }
+    }
+}
+
+/// A group moderation action
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupAction {
+    /// Add people to the group, or change their roles (kind 9000)
+    PutUser(Vec<(PublicKey, Vec<String>)>),
+
+    /// Remove people from the group (kind 9001)
+    RemoveUser(Vec<PublicKey>),
+
+    /// Change the group metadata (kind 9002)
+    EditMetadata(GroupMetadataEdit),
+
+    /// Delete events from the group (kind 9005)
+    DeleteEvent(Vec<Id>),
+
+    /// Create the group (kind 9007)
+    CreateGroup,
+
+    /// Delete the group (kind 9008)
+    DeleteGroup,
+
+    /// Create an invite code (kind 9009)
+    CreateInvite(String),
+}
+
+impl GroupAction {
+    /// The event kind
+    pub fn kind(&self) -> EventKind {
+        EventKind::GroupControl(match self {
+            GroupAction::PutUser(_) => 9000,
+            GroupAction::RemoveUser(_) => 9001,
+            GroupAction::EditMetadata(_) => 9002,
+            GroupAction::DeleteEvent(_) => 9005,
+            GroupAction::CreateGroup => 9007,
+            GroupAction::DeleteGroup => 9008,
+            GroupAction::CreateInvite(_) => 9009,
+        })
+    }
+}
+
+/// A NIP-29 group moderation event (kinds 9000-9020)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GroupModeration {
+    /// The group id (the 'h' tag)
+    pub group_id: String,
+
+    /// The action
+    pub action: GroupAction,
+
+    /// The first 8 hex characters of the ids of recent group events seen by the
+    /// author (the 'previous' tag), to detect out-of-context events
+    pub previous: Vec<String>,
+
+    /// The reason for the action
+    pub reason: String,
+}
+
+impl GroupModeration {
+    /// Create a new moderation event
+    pub fn new(group_id: String, action: GroupAction) -> GroupModeration {
+        GroupModeration {
+            group_id,
+            action,
+            previous: vec![],
+            reason: "".to_owned(),
+        }
+    }
+
+    /// Parse a group moderation event
+    pub fn from_event(event: &Event) -> Result<GroupModeration, Error> {
+        let kind = match event.kind {
+            EventKind::GroupControl(k) => k,
+            _ => return Err(Error::WrongEventKind),
+        };
+
+        let mut group_id: Option<String> = None;
+        let mut previous: Vec<String> = Vec::new();
+        let mut users: Vec<(PublicKey, Vec<String>)> = Vec::new();
+        let mut ids: Vec<Id> = Vec::new();
+        let mut code: Option<String> = None;
+        for tag in event.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Group(id)) => group_id = Some(id),
+                Ok(ParsedTag::Previous(p)) => previous = p,
+                Ok(ParsedTag::Event { id, .. }) => ids.push(id),
+                _ if tag.tagname() == "p" => users.extend(read_pubkey_and_roles(tag)),
+                _ if tag.tagname() == "code" => code = Some(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+
+        let action = match kind {
+            9000 => GroupAction::PutUser(users),
+            9001 => GroupAction::RemoveUser(users.into_iter().map(|(pk, _)| pk).collect()),
+            9002 => {
+                let mut edit = GroupMetadataEdit::default();
+                edit.read_tags(&event.tags);
+                GroupAction::EditMetadata(edit)
+            }
+            9005 => GroupAction::DeleteEvent(ids),
+            9007 => GroupAction::CreateGroup,
+            9008 => GroupAction::DeleteGroup,
+            9009 => GroupAction::CreateInvite(
+                code.ok_or_else(|| Error::Group("Missing 'code' tag".to_owned()))?,
+            ),
+            k => return Err(Error::Group(format!("Unsupported moderation kind {k}"))),
+        };
+
+        Ok(GroupModeration {
+            group_id: group_id.ok_or_else(|| Error::Group("Missing 'h' tag".to_owned()))?,
+            action,
+            previous,
+            reason: event.content.clone(),
+        })
+    }
+
+    /// Create the moderation PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![ParsedTag::Group(self.group_id.clone()).into_tag()];
+        match &self.action {
+            GroupAction::PutUser(users) => {
+                for (pubkey, roles) in users.iter() {
+                    tags.push(pubkey_and_roles_tag(pubkey, roles));
+                }
+            }
+            GroupAction::RemoveUser(users) => {
+                for pubkey in users.iter() {
+                    tags.push(Tag::new(&["p", &pubkey.as_hex_string()]));
+                }
+            }
+            GroupAction::EditMetadata(edit) => tags.extend(edit.tags()),
+            GroupAction::DeleteEvent(ids) => {
+                for id in ids.iter() {
+                    tags.push(Tag::new(&["e", &id.as_hex_string()]));
+                }
+            }
+            GroupAction::CreateGroup | GroupAction::DeleteGroup => {}
+            GroupAction::CreateInvite(code) => tags.push(Tag::new(&["code", code])),
+        }
+        if !self.previous.is_empty() {
+            tags.push(ParsedTag::Previous(self.previous.clone()).into_tag());
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: self.action.kind(),
+            tags,
+            content: self.reason.clone(),
+        }
+    }
+}
+
+/// The state of a group, built by replaying its moderation events
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GroupState {
+    /// The metadata
+    pub metadata: GroupMetadata,
+
+    /// Members and their roles. Members with any role are admins.
+    pub members: BTreeMap<PublicKey, Vec<String>>,
+
+    /// Events that were deleted
+    pub deleted_events: BTreeSet<Id>,
+
+    /// Invite codes
+    pub invites: BTreeSet<String>,
+
+    /// Whether the group has been created
+    pub created: bool,
+
+    /// Whether the group has been deleted
+    pub deleted: bool,
+}
+
+impl GroupState {
+    /// Create the state of a group before any moderation events
+    pub fn new(group_id: String) -> GroupState {
+        GroupState {
+            metadata: GroupMetadata {
+                id: group_id,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Whether someone may moderate the group: the relay, or a member with a role
+    pub fn is_admin(&self, pubkey: &PublicKey, relay_pubkey: &PublicKey) -> bool {
+        pubkey == relay_pubkey || self.members.get(pubkey).is_some_and(|r| !r.is_empty())
+    }
+
+    /// Apply a moderation action by `author`. Returns false, changing nothing, if
+    /// the action is not for this group or the author may not take it.
+    ///
+    /// Creating the group makes its creator an admin with the "admin" role.
+    pub fn apply(
+        &mut self,
+        moderation: &GroupModeration,
+        author: &PublicKey,
+        relay_pubkey: &PublicKey,
+    ) -> bool {
+        if moderation.group_id != self.metadata.id || self.deleted {
+            return false;
+        }
+        let allowed = match moderation.action {
+            GroupAction::CreateGroup => !self.created,
+            _ => self.is_admin(author, relay_pubkey),
+        };
+        if !allowed {
+            return false;
+        }
+
+        match &moderation.action {
+            GroupAction::PutUser(users) => {
+                for (pubkey, roles) in users.iter() {
+                    let _ = self.members.insert(*pubkey, roles.clone());
+                }
+            }
+            GroupAction::RemoveUser(users) => {
+                for pubkey in users.iter() {
+                    let _ = self.members.remove(pubkey);
+                }
+            }
+            GroupAction::EditMetadata(edit) => edit.apply_to(&mut self.metadata),
+            GroupAction::DeleteEvent(ids) => self.deleted_events.extend(ids.iter().copied()),
+            GroupAction::CreateGroup => {
+                self.created = true;
+                if author != relay_pubkey {
+                    let _ = self.members.insert(*author, vec!["admin".to_owned()]);
+                }
+            }
+            GroupAction::DeleteGroup => self.deleted = true,
+            GroupAction::CreateInvite(code) => {
+                let _ = self.invites.insert(code.clone());
+            }
+        }
+        true
+    }
+
+    /// Build the state of a group by replaying its moderation events in order (by
+    /// created_at, then id). Events that are not moderation events for this group,
+    /// or that the author was not allowed to take, are skipped.
+    ///
+    /// Signatures are not checked here.
+    pub fn replay(group_id: String, relay_pubkey: &PublicKey, events: &[Event]) -> GroupState {
+        let mut events: Vec<&Event> = events.iter().collect();
+        events.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+        let mut state = GroupState::new(group_id);
+        for event in events {
+            if let Ok(moderation) = GroupModeration::from_event(event) {
+                let _ = state.apply(&moderation, &event.pubkey, relay_pubkey);
+            }
+        }
+        state
+    }
+}
+
+fn read_pubkey_and_roles(tag: &Tag) -> Option<(PublicKey, Vec<String>)> {
+    let pubkey = PublicKey::try_from_hex_string(tag.value(), true).ok()?;
+    let roles = (2..tag.len())
+        .map(|i| tag.get_index(i).to_owned())
+        .filter(|r| !r.is_empty())
+        .collect();
+    Some((pubkey, roles))
+}
+
+fn pubkey_and_roles_tag(pubkey: &PublicKey, roles: &[String]) -> Tag {
+    let mut tag = Tag::new(&["p", &pubkey.as_hex_string()]);
+    tag.push_values(roles.to_vec());
+    tag
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_group_moderation_round_trip() {
+        let private_key = PrivateKey::generate();
+        let actions = vec![
+            GroupAction::PutUser(vec![(PublicKey::mock(), vec!["moderator".to_owned()])]),
+            GroupAction::RemoveUser(vec![PublicKey::mock()]),
+            GroupAction::EditMetadata(GroupMetadataEdit {
+                name: Some("Pizza Lovers".to_owned()),
+                private: Some(true),
+                ..Default::default()
+            }),
+            GroupAction::DeleteEvent(vec![Id::mock()]),
+            GroupAction::CreateGroup,
+            GroupAction::DeleteGroup,
+            GroupAction::CreateInvite("abc123".to_owned()),
+        ];
+        for action in actions {
+            let mut moderation = GroupModeration::new("pizza".to_owned(), action);
+            moderation.previous = vec!["a1b2c3d4".to_owned()];
+            let event = private_key
+                .sign_event(moderation.to_pre_event(private_key.public_key()))
+                .unwrap();
+            assert_eq!(event.group_id().as_deref(), Some("pizza"));
+            assert_eq!(GroupModeration::from_event(&event).unwrap(), moderation);
+        }
+
+        let relay = PrivateKey::generate();
+        let list = GroupList::Admins(vec![(PublicKey::mock(), vec!["ceo".to_owned()])]);
+        let event = relay
+            .sign_event(list.to_pre_event(relay.public_key(), "pizza"))
+            .unwrap();
+        assert_eq!(
+            GroupList::from_event(&event).unwrap(),
+            ("pizza".to_owned(), list)
+        );
+    }
+
+    #[test]
+    fn test_group_state_replay() {
+        let relay = PrivateKey::generate();
+        let founder = PrivateKey::generate();
+        let member = PrivateKey::generate();
+
+        let moderation = |key: &PrivateKey, action: GroupAction, at: i64| {
+            let mut pre_event =
+                GroupModeration::new("pizza".to_owned(), action).to_pre_event(key.public_key());
+            pre_event.created_at = Unixtime(at);
+            key.sign_event(pre_event).unwrap()
+        };
+
+        let events = vec![
+            // Out of order on purpose
+            moderation(
+                &founder,
+                GroupAction::PutUser(vec![(member.public_key(), vec![])]),
+                200,
+            ),
+            moderation(&founder, GroupAction::CreateGroup, 100),
+            // A member without a role can't moderate
+            moderation(
+                &member,
+                GroupAction::RemoveUser(vec![founder.public_key()]),
+                300,
+            ),
+            moderation(
+                &founder,
+                GroupAction::EditMetadata(GroupMetadataEdit {
+                    name: Some("Pizza Lovers".to_owned()),
+                    about: Some("All about pizza".to_owned()),
+                    closed: Some(true),
+                    ..Default::default()
+                }),
+                400,
+            ),
+            // A partial edit leaves the other fields alone
+            moderation(
+                &founder,
+                GroupAction::EditMetadata(GroupMetadataEdit {
+                    name: Some("Pizza Fans".to_owned()),
+                    ..Default::default()
+                }),
+                450,
+            ),
+            moderation(&relay, GroupAction::CreateInvite("xyz".to_owned()), 500),
+        ];
+
+        let state = GroupState::replay("pizza".to_owned(), &relay.public_key(), &events);
+        assert!(state.created);
+        assert_eq!(state.metadata.id, "pizza");
+        assert_eq!(state.metadata.name.as_deref(), Some("Pizza Fans"));
+        assert_eq!(state.metadata.about.as_deref(), Some("All about pizza"));
+        assert!(state.metadata.closed);
+        assert_eq!(state.members.len(), 2);
+        assert!(state.is_admin(&founder.public_key(), &relay.public_key()));
+        assert!(!state.is_admin(&member.public_key(), &relay.public_key()));
+        assert!(state.invites.contains("xyz"));
+    }
+}
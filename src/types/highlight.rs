@@ -0,0 +1,267 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, Span, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+
+/// Where a highlight was taken from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HighlightSource {
+    /// A nostr event, with a relay where it may be found
+    Event(Id, Option<UncheckedUrl>),
+
+    /// An addressable nostr event, such as an article
+    Address(NAddr),
+
+    /// Something outside of nostr
+    Url(UncheckedUrl),
+}
+
+/// Someone credited for highlighted content
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HighlightAuthor {
+    /// Who
+    pub pubkey: PublicKey,
+
+    /// A relay where they may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// Their role, such as "author" or "editor"
+    pub role: Option<String>,
+}
+
+/// A NIP-84 highlight (kind 9802)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Highlight {
+    /// The highlighted text
+    pub content: String,
+
+    /// The text surrounding the highlighted text
+    pub context: Option<String>,
+
+    /// Where the highlighted text came from
+    pub source: Option<HighlightSource>,
+
+    /// Who is credited for the highlighted text
+    pub authors: Vec<HighlightAuthor>,
+}
+
+impl Highlight {
+    /// Create a new highlight
+    pub fn new(content: String) -> Highlight {
+        Highlight {
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// Highlight the bytes of `source`'s content within `span`.
+    ///
+    /// The context is the paragraph (the text between blank lines) around the
+    /// span, if it is more than the highlighted text. The source is the event, or
+    /// its address if it is parameterized replaceable, and its author is credited
+    /// as "author".
+    #[allow(clippy::string_slice)] // the span is checked
+    pub fn from_source(source: &Event, span: Span) -> Result<Highlight, Error> {
+        let content = &source.content;
+        let (start, end) = (span.start(), span.end());
+        if start >= end
+            || end > content.len()
+            || !content.is_char_boundary(start)
+            || !content.is_char_boundary(end)
+        {
+            return Err(Error::Highlight(
+                "Span is not a part of the content".to_owned(),
+            ));
+        }
+
+        let text = &content[start..end];
+        let context_start = content[..start].rfind("\n\n").map(|i| i + 2).unwrap_or(0);
+        let context_end = content[end..]
+            .find("\n\n")
+            .map(|i| end + i)
+            .unwrap_or(content.len());
+        let context = content[context_start..context_end].trim();
+
+        let source_ref = if source.kind.is_parameterized_replaceable() {
+            HighlightSource::Address(NAddr {
+                d: source.parameter().unwrap_or_default(),
+                relays: vec![],
+                kind: source.kind,
+                author: source.pubkey,
+            })
+        } else {
+            HighlightSource::Event(source.id, None)
+        };
+
+        Ok(Highlight {
+            content: text.to_owned(),
+            context: if context == text.trim() {
+                None
+            } else {
+                Some(context.to_owned())
+            },
+            source: Some(source_ref),
+            authors: vec![HighlightAuthor {
+                pubkey: source.pubkey,
+                relay: None,
+                role: Some("author".to_owned()),
+            }],
+        })
+    }
+
+    /// Parse a kind-9802 Highlights event.
+    ///
+    /// The source is the first 'e' or 'a' tag, or else an 'r' tag marked "source"
+    /// (or unmarked).
+    pub fn from_event(event: &Event) -> Result<Highlight, Error> {
+        if event.kind != EventKind::Highlights {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut highlight = Highlight::new(event.content.clone());
+        let mut url: Option<UncheckedUrl> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "context" => highlight.context = Some(tag.value().to_owned()),
+                "r" if url.is_none() && matches!(tag.get_index(2), "" | "source") => {
+                    url = Some(UncheckedUrl(tag.value().to_owned()));
+                }
+                "p" => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        highlight.authors.push(HighlightAuthor {
+                            pubkey,
+                            relay: tag.get_opt_index(2).map(|r| UncheckedUrl(r.to_owned())),
+                            role: tag.get_opt_index(3).map(|r| r.to_owned()),
+                        });
+                    }
+                }
+                _ if highlight.source.is_none() => match tag.parse() {
+                    Ok(ParsedTag::Event {
+                        id,
+                        recommended_relay_url,
+                        ..
+                    }) => {
+                        highlight.source = Some(HighlightSource::Event(id, recommended_relay_url))
+                    }
+                    Ok(ParsedTag::Address { address, .. }) => {
+                        highlight.source = Some(HighlightSource::Address(address))
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        if highlight.source.is_none() {
+            highlight.source = url.map(HighlightSource::Url);
+        }
+        Ok(highlight)
+    }
+
+    /// Create the kind-9802 Highlights PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags: Vec<Tag> = Vec::new();
+        match &self.source {
+            Some(HighlightSource::Event(id, relay)) => tags.push(
+                ParsedTag::Event {
+                    id: *id,
+                    recommended_relay_url: relay.clone(),
+                    marker: None,
+                    author_pubkey: None,
+                }
+                .into_tag(),
+            ),
+            Some(HighlightSource::Address(address)) => tags.push(
+                ParsedTag::Address {
+                    address: address.clone(),
+                    marker: None,
+                }
+                .into_tag(),
+            ),
+            Some(HighlightSource::Url(url)) => tags.push(Tag::new(&["r", &url.0, "source"])),
+            None => {}
+        }
+        if let Some(context) = &self.context {
+            tags.push(Tag::new(&["context", context]));
+        }
+        for author in self.authors.iter() {
+            let mut tag = Tag::new(&["p", &author.pubkey.as_hex_string()]);
+            tag.set_index(
+                2,
+                author
+                    .relay
+                    .as_ref()
+                    .map(|r| r.0.clone())
+                    .unwrap_or_default(),
+            );
+            if let Some(role) = &author.role {
+                tag.set_index(3, role.clone());
+            }
+            tags.push(tag);
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::Highlights,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Article, PrivateKey, Signer};
+
+    #[test]
+    fn test_highlight_from_source() {
+        let author = PrivateKey::generate();
+        let reader = PrivateKey::generate();
+
+        let article = Article::new(
+            "essay".to_owned(),
+            "# Essay\n\nFirst paragraph.\n\nThe quick brown fox jumps. Over the lazy dog.\n\nEnd."
+                .to_owned(),
+        );
+        let source = author
+            .sign_event(article.to_pre_event(author.public_key()))
+            .unwrap();
+        let start = source.content.find("The quick").unwrap();
+        let span = Span::new(start, start + "The quick brown fox jumps.".len());
+
+        let highlight = Highlight::from_source(&source, span).unwrap();
+        assert_eq!(highlight.content, "The quick brown fox jumps.");
+        assert_eq!(
+            highlight.context.as_deref(),
+            Some("The quick brown fox jumps. Over the lazy dog.")
+        );
+        assert_eq!(
+            highlight.source,
+            Some(HighlightSource::Address(
+                article.address(author.public_key())
+            ))
+        );
+
+        let event = reader
+            .sign_event(highlight.to_pre_event(reader.public_key()))
+            .unwrap();
+        assert_eq!(Highlight::from_event(&event).unwrap(), highlight);
+
+        assert!(Highlight::from_source(&source, Span::new(3, 3)).is_err());
+        assert!(Highlight::from_source(&source, Span::new(0, 1000)).is_err());
+    }
+
+    #[test]
+    fn test_highlight_url_source() {
+        let private_key = PrivateKey::generate();
+        let mut highlight = Highlight::new("Information wants to be free".to_owned());
+        highlight.source = Some(HighlightSource::Url(UncheckedUrl(
+            "https://example.com/essay".to_owned(),
+        )));
+        let event = private_key
+            .sign_event(highlight.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(Highlight::from_event(&event).unwrap(), highlight);
+    }
+}
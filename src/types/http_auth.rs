@@ -0,0 +1,196 @@
+use crate::{Error, Event, EventKind, PreEvent, PublicKey, Signer, Tag, Unixtime, Url};
+use base64::Engine;
+
+/// A NIP-98 HTTP Auth request description
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpAuth {
+    /// The absolute request URL, including query parameters
+    pub url: Url,
+
+    /// The HTTP method (uppercase)
+    pub method: String,
+
+    /// The sha256 hash (hex) of the request body, if there is one
+    pub payload: Option<String>,
+}
+
+impl HttpAuth {
+    /// Describe a request. The body, if given, is hashed into the payload.
+    pub fn new(url: Url, method: &str, body: Option<&[u8]>) -> HttpAuth {
+        HttpAuth {
+            url,
+            method: method.to_uppercase(),
+            payload: body.map(sha256_hex),
+        }
+    }
+
+    /// Create and sign the kind-27235 HttpAuth event
+    pub fn create_event<S>(&self, signer: &S) -> Result<Event, Error>
+    where
+        S: Signer,
+    {
+        let mut tags = vec![
+            Tag::new(&["u", self.url.as_str()]),
+            Tag::new(&["method", &self.method]),
+        ];
+        if let Some(payload) = &self.payload {
+            tags.push(Tag::new(&["payload", payload]));
+        }
+
+        signer.sign_event(PreEvent {
+            pubkey: signer.public_key(),
+            created_at: Unixtime::now(),
+            kind: EventKind::HttpAuth,
+            tags,
+            content: "".to_owned(),
+        })
+    }
+
+    /// Create and sign the event, and return the HTTP `Authorization` header value
+    pub fn create_authorization_header<S>(&self, signer: &S) -> Result<String, Error>
+    where
+        S: Signer,
+    {
+        let event = self.create_event(signer)?;
+        HttpAuth::authorization_header(&event)
+    }
+
+    /// The HTTP `Authorization` header value for a signed event
+    pub fn authorization_header(event: &Event) -> Result<String, Error> {
+        let json = serde_json::to_string(event)?;
+        Ok(format!(
+            "Nostr {}",
+            base64::engine::general_purpose::STANDARD.encode(json)
+        ))
+    }
+
+    /// Read the event from an HTTP `Authorization` header value
+    pub fn event_from_header(header: &str) -> Result<Event, Error> {
+        let mut parts = header.trim().splitn(2, ' ');
+        let scheme = parts.next().unwrap_or("");
+        if !scheme.eq_ignore_ascii_case("nostr") {
+            return Err(Error::HttpAuth("Not a Nostr authorization".to_owned()));
+        }
+        let encoded = parts.next().unwrap_or("").trim();
+        let json = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+
+    /// Verify an HTTP `Authorization` header value against the request that
+    /// carried it. See `verify_event()`.
+    pub fn verify_header(
+        header: &str,
+        url: &Url,
+        method: &str,
+        body: Option<&[u8]>,
+        window_secs: i64,
+    ) -> Result<PublicKey, Error> {
+        let event = HttpAuth::event_from_header(header)?;
+        HttpAuth::verify_event(&event, url, method, body, window_secs)
+    }
+
+    /// Verify an HttpAuth event against the request it authorizes.
+    ///
+    /// This checks the signature and kind, that the event was created within
+    /// `window_secs` of now, that the URL and method match, and (if the request has
+    /// a body) that the payload hash matches. Returns the authenticated public key.
+    pub fn verify_event(
+        event: &Event,
+        url: &Url,
+        method: &str,
+        body: Option<&[u8]>,
+        window_secs: i64,
+    ) -> Result<PublicKey, Error> {
+        if event.kind != EventKind::HttpAuth {
+            return Err(Error::WrongEventKind);
+        }
+        event.verify(None)?;
+
+        let now = Unixtime::now();
+        if now.0.abs_diff(event.created_at.0) > window_secs.unsigned_abs() {
+            return Err(Error::HttpAuth(
+                "Event is outside the time window".to_owned(),
+            ));
+        }
+
+        let mut event_url: Option<Url> = None;
+        let mut event_method: Option<&str> = None;
+        let mut event_payload: Option<&str> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "u" if event_url.is_none() => event_url = Some(Url::try_from_str(tag.value())?),
+                "method" if event_method.is_none() => event_method = Some(tag.value()),
+                "payload" if event_payload.is_none() => event_payload = Some(tag.value()),
+                _ => {}
+            }
+        }
+
+        if event_url.as_ref() != Some(url) {
+            return Err(Error::HttpAuth("URL does not match".to_owned()));
+        }
+        match event_method {
+            Some(m) if m.eq_ignore_ascii_case(method) => {}
+            _ => return Err(Error::HttpAuth("Method does not match".to_owned())),
+        }
+        if let Some(body) = body {
+            match event_payload {
+                Some(p) if p.eq_ignore_ascii_case(&sha256_hex(body)) => {}
+                _ => return Err(Error::HttpAuth("Payload does not match".to_owned())),
+            }
+        }
+
+        Ok(event.pubkey)
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(data))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PrivateKey;
+
+    #[test]
+    fn test_http_auth() {
+        let private_key = PrivateKey::generate();
+        let url = Url::try_from_str("https://API.example.com/upload?x=1").unwrap();
+        let body = b"{\"hello\":\"world\"}";
+
+        let header = HttpAuth::new(url.clone(), "post", Some(body))
+            .create_authorization_header(&private_key)
+            .unwrap();
+        assert!(header.starts_with("Nostr "));
+
+        // The URL is normalized on both sides
+        let server_url = Url::try_from_str("https://api.example.com/upload?x=1").unwrap();
+        let pubkey = HttpAuth::verify_header(&header, &server_url, "POST", Some(body), 60).unwrap();
+        assert_eq!(pubkey, private_key.public_key());
+
+        let other_url = Url::try_from_str("https://api.example.com/upload?x=2").unwrap();
+        assert!(HttpAuth::verify_header(&header, &other_url, "POST", Some(body), 60).is_err());
+        assert!(HttpAuth::verify_header(&header, &server_url, "PUT", Some(body), 60).is_err());
+        assert!(HttpAuth::verify_header(&header, &server_url, "POST", Some(b"{}"), 60).is_err());
+        assert!(HttpAuth::verify_header("Basic abc", &server_url, "POST", None, 60).is_err());
+    }
+
+    #[test]
+    fn test_http_auth_extreme_created_at() {
+        let private_key = PrivateKey::generate();
+        let url = Url::try_from_str("https://api.example.com/upload").unwrap();
+        for created_at in [i64::MIN, i64::MAX] {
+            let event = private_key
+                .sign_event(PreEvent {
+                    pubkey: private_key.public_key(),
+                    created_at: Unixtime(created_at),
+                    kind: EventKind::HttpAuth,
+                    tags: vec![Tag::new(&["u", url.as_str()]), Tag::new(&["method", "GET"])],
+                    content: "".to_owned(),
+                })
+                .unwrap();
+            assert!(HttpAuth::verify_event(&event, &url, "GET", None, 60).is_err());
+        }
+    }
+}
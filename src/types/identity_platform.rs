@@ -0,0 +1,108 @@
+use crate::{PublicKey, UncheckedUrl};
+
+/// A platform on which a NIP-39 external identity can be claimed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IdentityPlatform {
+    /// GitHub. The identity is a username and the proof is a gist id.
+    GitHub,
+
+    /// Twitter. The identity is a username and the proof is a tweet id.
+    Twitter,
+
+    /// Mastodon. The identity is `instance/@username` and the proof is a post id.
+    Mastodon,
+
+    /// Telegram. The identity is a user id and the proof is `channel/message-id`.
+    Telegram,
+}
+
+impl IdentityPlatform {
+    /// The platform name used in 'i' tags
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            IdentityPlatform::GitHub => "github",
+            IdentityPlatform::Twitter => "twitter",
+            IdentityPlatform::Mastodon => "mastodon",
+            IdentityPlatform::Telegram => "telegram",
+        }
+    }
+
+    /// Parse the platform name used in 'i' tags
+    pub fn from_str_opt(s: &str) -> Option<IdentityPlatform> {
+        match s {
+            "github" => Some(IdentityPlatform::GitHub),
+            "twitter" => Some(IdentityPlatform::Twitter),
+            "mastodon" => Some(IdentityPlatform::Mastodon),
+            "telegram" => Some(IdentityPlatform::Telegram),
+            _ => None,
+        }
+    }
+
+    /// The URL where the proof of an identity claim can be found
+    pub fn proof_url(&self, identity: &str, proof: &str) -> UncheckedUrl {
+        UncheckedUrl(match *self {
+            IdentityPlatform::GitHub => format!("https://gist.github.com/{identity}/{proof}"),
+            IdentityPlatform::Twitter => format!("https://twitter.com/{identity}/status/{proof}"),
+            IdentityPlatform::Mastodon => format!("https://{identity}/{proof}"),
+            IdentityPlatform::Telegram => format!("https://t.me/{proof}"),
+        })
+    }
+
+    /// The text the proof must contain for `pubkey`
+    pub fn proof_text(&self, pubkey: &PublicKey) -> String {
+        let npub = pubkey.as_bech32_string();
+        match *self {
+            IdentityPlatform::GitHub => {
+                format!("Verifying that I control the following Nostr public key: {npub}")
+            }
+            IdentityPlatform::Twitter => {
+                format!("Verifying my account on nostr My Public Key: \"{npub}\"")
+            }
+            IdentityPlatform::Mastodon | IdentityPlatform::Telegram => {
+                format!("Verifying that I control the following Nostr public key: \"{npub}\"")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EventKind, PreEvent, PrivateKey, Signer, Tag, Unixtime};
+
+    #[test]
+    fn test_external_identities() {
+        let private_key = PrivateKey::generate();
+        let event = private_key
+            .sign_event(PreEvent {
+                pubkey: private_key.public_key(),
+                created_at: Unixtime::mock(),
+                kind: EventKind::Metadata,
+                tags: vec![
+                    Tag::new(&["i", "github:semisol", "9721ce4ee4fceb91c9711ca2a6c9a5ab"]),
+                    Tag::new(&["i", "telegram:1087295469", "nostrdirectory/770"]),
+                    Tag::new(&["i", "myspace:tom", "1"]),
+                ],
+                content: "{}".to_owned(),
+            })
+            .unwrap();
+
+        let identities = event.external_identities();
+        assert_eq!(identities.len(), 2);
+
+        let (platform, identity, proof) = &identities[0];
+        assert_eq!(*platform, IdentityPlatform::GitHub);
+        assert_eq!(
+            platform.proof_url(identity, proof).0,
+            "https://gist.github.com/semisol/9721ce4ee4fceb91c9711ca2a6c9a5ab"
+        );
+        let (platform, identity, proof) = &identities[1];
+        assert_eq!(
+            platform.proof_url(identity, proof).0,
+            "https://t.me/nostrdirectory/770"
+        );
+
+        let text = platform.proof_text(&private_key.public_key());
+        assert!(text.contains(&private_key.public_key().as_bech32_string()));
+    }
+}
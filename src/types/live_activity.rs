@@ -0,0 +1,421 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, Signature, Signer, Tag,
+    UncheckedUrl, Unixtime,
+};
+use crate::Error;
+
+/// The status of a live activity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LiveActivityStatus {
+    /// Not started yet
+    Planned,
+
+    /// Happening now
+    Live,
+
+    /// Over
+    Ended,
+}
+
+impl LiveActivityStatus {
+    /// The 'status' tag value
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            LiveActivityStatus::Planned => "planned",
+            LiveActivityStatus::Live => "live",
+            LiveActivityStatus::Ended => "ended",
+        }
+    }
+
+    /// Parse a 'status' tag value
+    pub fn from_str_opt(s: &str) -> Option<LiveActivityStatus> {
+        match s {
+            "planned" => Some(LiveActivityStatus::Planned),
+            "live" => Some(LiveActivityStatus::Live),
+            "ended" => Some(LiveActivityStatus::Ended),
+            _ => None,
+        }
+    }
+}
+
+/// A person taking part in a live activity
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveParticipant {
+    /// Who
+    pub pubkey: PublicKey,
+
+    /// A relay where they may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// Their role, e.g. "Host" or "Speaker"
+    pub role: Option<String>,
+
+    /// Their signature over the activity address, proving they agreed to take part
+    pub proof: Option<Signature>,
+}
+
+/// A NIP-53 live activity (kind 30311)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LiveActivity {
+    /// The 'd' identifier
+    pub d: String,
+
+    /// The title
+    pub title: Option<String>,
+
+    /// A description
+    pub summary: Option<String>,
+
+    /// A preview image
+    pub image: Option<UncheckedUrl>,
+
+    /// Where to watch it live
+    pub streaming: Option<UncheckedUrl>,
+
+    /// Where to watch it afterwards
+    pub recording: Option<UncheckedUrl>,
+
+    /// The status
+    pub status: Option<LiveActivityStatus>,
+
+    /// When it starts
+    pub starts: Option<Unixtime>,
+
+    /// When it ends
+    pub ends: Option<Unixtime>,
+
+    /// The people taking part
+    pub participants: Vec<LiveParticipant>,
+
+    /// How many people are taking part now
+    pub current_participants: Option<u64>,
+
+    /// How many people have taken part in total
+    pub total_participants: Option<u64>,
+
+    /// Hashtags
+    pub hashtags: Vec<String>,
+
+    /// Relays where the live chat is
+    pub relays: Vec<UncheckedUrl>,
+}
+
+impl LiveActivity {
+    /// Create a new live activity
+    pub fn new(d: String) -> LiveActivity {
+        LiveActivity {
+            d,
+            ..Default::default()
+        }
+    }
+
+    /// The address of this live activity, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: EventKind::LiveEvent,
+            author,
+        }
+    }
+
+    /// Parse a kind-30311 LiveEvent event. Participants with malformed proofs are
+    /// kept, without a proof.
+    pub fn from_event(event: &Event) -> Result<LiveActivity, Error> {
+        if event.kind != EventKind::LiveEvent {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut activity = LiveActivity::default();
+        for tag in event.tags.iter() {
+            let value = tag.value();
+            match tag.tagname() {
+                "d" => activity.d = value.to_owned(),
+                "title" => activity.title = Some(value.to_owned()),
+                "summary" => activity.summary = Some(value.to_owned()),
+                "image" => activity.image = Some(UncheckedUrl(value.to_owned())),
+                "streaming" => activity.streaming = Some(UncheckedUrl(value.to_owned())),
+                "recording" => activity.recording = Some(UncheckedUrl(value.to_owned())),
+                "status" => activity.status = LiveActivityStatus::from_str_opt(value),
+                "starts" => activity.starts = value.parse::<i64>().ok().map(Unixtime),
+                "ends" => activity.ends = value.parse::<i64>().ok().map(Unixtime),
+                "current_participants" => activity.current_participants = value.parse().ok(),
+                "total_participants" => activity.total_participants = value.parse().ok(),
+                "t" => activity.hashtags.push(value.to_owned()),
+                "relays" => {
+                    for i in 1..tag.len() {
+                        activity
+                            .relays
+                            .push(UncheckedUrl(tag.get_index(i).to_owned()));
+                    }
+                }
+                "p" => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(value, true) {
+                        activity.participants.push(LiveParticipant {
+                            pubkey,
+                            relay: tag.get_opt_index(2).map(|s| UncheckedUrl(s.to_owned())),
+                            role: tag.get_opt_index(3).map(|s| s.to_owned()),
+                            proof: tag
+                                .get_opt_index(4)
+                                .and_then(|s| Signature::try_from_hex_string(s).ok()),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(activity)
+    }
+
+    /// Create the kind-30311 LiveEvent PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d])];
+        let strings = [
+            ("title", self.title.as_ref()),
+            ("summary", self.summary.as_ref()),
+            ("image", self.image.as_ref().map(|u| &u.0)),
+            ("streaming", self.streaming.as_ref().map(|u| &u.0)),
+            ("recording", self.recording.as_ref().map(|u| &u.0)),
+        ];
+        for (name, value) in strings.iter() {
+            if let Some(value) = value {
+                tags.push(Tag::new(&[name, value]));
+            }
+        }
+        if let Some(status) = self.status {
+            tags.push(Tag::new(&["status", status.as_str()]));
+        }
+        let numbers = [
+            ("starts", self.starts.map(|t| t.0.to_string())),
+            ("ends", self.ends.map(|t| t.0.to_string())),
+            (
+                "current_participants",
+                self.current_participants.map(|n| n.to_string()),
+            ),
+            (
+                "total_participants",
+                self.total_participants.map(|n| n.to_string()),
+            ),
+        ];
+        for (name, value) in numbers.iter() {
+            if let Some(value) = value {
+                tags.push(Tag::new(&[name, value]));
+            }
+        }
+        for participant in self.participants.iter() {
+            let mut tag = Tag::new(&["p", &participant.pubkey.as_hex_string()]);
+            tag.set_index(
+                2,
+                participant
+                    .relay
+                    .as_ref()
+                    .map(|r| r.0.clone())
+                    .unwrap_or_default(),
+            );
+            tag.set_index(3, participant.role.clone().unwrap_or_default());
+            if let Some(proof) = &participant.proof {
+                tag.set_index(4, proof.as_hex_string());
+            }
+            tags.push(tag);
+        }
+        for hashtag in self.hashtags.iter() {
+            tags.push(Tag::new(&["t", hashtag]));
+        }
+        if !self.relays.is_empty() {
+            let mut tag = Tag::new(&["relays"]);
+            tag.push_values(self.relays.iter().map(|r| r.0.clone()).collect());
+            tags.push(tag);
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::LiveEvent,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+
+    /// Create a participant's proof that they agreed to take part in the activity at
+    /// `address`: their signature over the address
+    pub fn create_proof<S>(signer: &S, address: &NAddr) -> Result<Signature, Error>
+    where
+        S: Signer,
+    {
+        signer.sign(address_tag_value(address).as_bytes())
+    }
+
+    /// Verify a participant's proof, given the activity's author
+    pub fn verify_proof(
+        &self,
+        author: PublicKey,
+        participant: &LiveParticipant,
+    ) -> Result<(), Error> {
+        let proof = participant
+            .proof
+            .as_ref()
+            .ok_or_else(|| Error::LiveActivity("Participant has no proof".to_owned()))?;
+        let address = address_tag_value(&self.address(author));
+        participant.pubkey.verify(address.as_bytes(), proof)
+    }
+
+    /// The participants whose proofs are valid, given the activity's author
+    pub fn verified_participants(&self, author: PublicKey) -> Vec<&LiveParticipant> {
+        self.participants
+            .iter()
+            .filter(|p| self.verify_proof(author, p).is_ok())
+            .collect()
+    }
+}
+
+/// A NIP-53 live chat message (kind 1311)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveChatMessage {
+    /// The live activity being chatted about
+    pub activity: NAddr,
+
+    /// The message being replied to, if any
+    pub reply_to: Option<Id>,
+
+    /// The message
+    pub content: String,
+}
+
+impl LiveChatMessage {
+    /// Create a new chat message about a live activity
+    pub fn new(activity: NAddr, content: String) -> LiveChatMessage {
+        LiveChatMessage {
+            activity,
+            reply_to: None,
+            content,
+        }
+    }
+
+    /// Parse a kind-1311 LiveChatMessage event
+    pub fn from_event(event: &Event) -> Result<LiveChatMessage, Error> {
+        if event.kind != EventKind::LiveChatMessage {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut activity: Option<NAddr> = None;
+        let mut reply_to: Option<Id> = None;
+        for tag in event.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Address { address, .. })
+                    if activity.is_none() && address.kind == EventKind::LiveEvent =>
+                {
+                    activity = Some(address)
+                }
+                Ok(ParsedTag::Event { id, .. }) if reply_to.is_none() => reply_to = Some(id),
+                _ => {}
+            }
+        }
+
+        Ok(LiveChatMessage {
+            activity: activity
+                .ok_or_else(|| Error::LiveActivity("Missing activity 'a' tag".to_owned()))?,
+            reply_to,
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the kind-1311 LiveChatMessage PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![ParsedTag::Address {
+            address: self.activity.clone(),
+            marker: Some("root".to_owned()),
+        }
+        .into_tag()];
+        if let Some(id) = self.reply_to {
+            tags.push(
+                ParsedTag::Event {
+                    id,
+                    recommended_relay_url: None,
+                    marker: None,
+                    author_pubkey: None,
+                }
+                .into_tag(),
+            );
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::LiveChatMessage,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+// The 'a' tag value of an address, "<kind>:<author>:<d>"
+fn address_tag_value(address: &NAddr) -> String {
+    ParsedTag::Address {
+        address: address.clone(),
+        marker: None,
+    }
+    .into_tag()
+    .value()
+    .to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PrivateKey;
+
+    #[test]
+    fn test_live_activity() {
+        let host = PrivateKey::generate();
+        let speaker = PrivateKey::generate();
+        let liar = PrivateKey::generate();
+
+        let mut activity = LiveActivity::new("stream".to_owned());
+        activity.title = Some("Live coding".to_owned());
+        activity.streaming = Some(UncheckedUrl("https://example.com/live.m3u8".to_owned()));
+        activity.status = Some(LiveActivityStatus::Live);
+        activity.starts = Some(Unixtime(1_700_000_000));
+        activity.current_participants = Some(12);
+        activity
+            .relays
+            .push(UncheckedUrl("wss://relay.example.com".to_owned()));
+
+        let address = activity.address(host.public_key());
+        let other_address = LiveActivity::new("other".to_owned()).address(host.public_key());
+        activity.participants.push(LiveParticipant {
+            pubkey: speaker.public_key(),
+            relay: None,
+            role: Some("Speaker".to_owned()),
+            proof: Some(LiveActivity::create_proof(&speaker, &address).unwrap()),
+        });
+        activity.participants.push(LiveParticipant {
+            pubkey: liar.public_key(),
+            relay: None,
+            role: Some("Host".to_owned()),
+            proof: Some(LiveActivity::create_proof(&liar, &other_address).unwrap()),
+        });
+
+        let event = host
+            .sign_event(activity.to_pre_event(host.public_key()))
+            .unwrap();
+        let parsed = LiveActivity::from_event(&event).unwrap();
+        assert_eq!(parsed, activity);
+
+        let verified = parsed.verified_participants(event.pubkey);
+        assert_eq!(verified.len(), 1);
+        assert_eq!(verified[0].pubkey, speaker.public_key());
+    }
+
+    #[test]
+    fn test_live_chat_message() {
+        let private_key = PrivateKey::generate();
+        let address = LiveActivity::new("stream".to_owned()).address(PublicKey::mock());
+        let mut message = LiveChatMessage::new(address, "Hello".to_owned());
+        message.reply_to = Some(Id::mock());
+
+        let event = private_key
+            .sign_event(message.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(LiveChatMessage::from_event(&event).unwrap(), message);
+    }
+}
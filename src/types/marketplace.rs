@@ -0,0 +1,808 @@
+use crate::types::{
+    ContentEncryptionAlgorithm, Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey,
+    Signer, Tag, Unixtime,
+};
+use crate::Error;
+use serde::{Deserialize, Serialize};
+
+/// A shipping zone of a NIP-15 stall
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ShippingZone {
+    /// The zone id, which products and orders refer to
+    pub id: String,
+
+    /// The zone name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The base cost of shipping to this zone, in the stall's currency
+    pub cost: f64,
+
+    /// The regions in this zone
+    #[serde(default)]
+    pub regions: Vec<String>,
+}
+
+/// A NIP-15 stall (kind 30017), the JSON content of a CreateUpdateStall event
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stall {
+    /// The stall id, which is also the 'd' tag
+    pub id: String,
+
+    /// The stall name
+    pub name: String,
+
+    /// A description of the stall
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The currency prices are in
+    pub currency: String,
+
+    /// Where the stall ships to
+    #[serde(default)]
+    pub shipping: Vec<ShippingZone>,
+}
+
+impl Stall {
+    /// The address of this stall, as published by `merchant`
+    pub fn address(&self, merchant: PublicKey) -> NAddr {
+        NAddr {
+            d: self.id.clone(),
+            relays: vec![],
+            kind: EventKind::CreateUpdateStall,
+            author: merchant,
+        }
+    }
+
+    /// Parse a kind-30017 CreateUpdateStall event
+    pub fn from_event(event: &Event) -> Result<Stall, Error> {
+        if event.kind != EventKind::CreateUpdateStall {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(serde_json::from_str(&event.content)?)
+    }
+
+    /// Create the kind-30017 CreateUpdateStall PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::CreateUpdateStall,
+            tags: vec![Tag::new(&["d", &self.id])],
+            content: serde_json::to_string(self)?,
+        })
+    }
+}
+
+/// The extra cost of shipping a product to one of its stall's shipping zones
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProductShipping {
+    /// The shipping zone id
+    pub id: String,
+
+    /// The extra cost, on top of the zone's base cost
+    pub cost: f64,
+}
+
+/// A NIP-15 product (kind 30018), the JSON content of a CreateUpdateProduct event
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Product {
+    /// The product id, which is also the 'd' tag
+    pub id: String,
+
+    /// The stall the product is sold from
+    pub stall_id: String,
+
+    /// The product name
+    pub name: String,
+
+    /// A description of the product
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Image urls
+    #[serde(default)]
+    pub images: Vec<String>,
+
+    /// The currency the price is in
+    pub currency: String,
+
+    /// The price
+    pub price: f64,
+
+    /// How many are available, or None if unlimited
+    #[serde(default)]
+    pub quantity: Option<u64>,
+
+    /// Specifications, as (key, value) pairs
+    #[serde(default)]
+    pub specs: Vec<(String, String)>,
+
+    /// Extra shipping costs per shipping zone
+    #[serde(default)]
+    pub shipping: Vec<ProductShipping>,
+
+    /// Categories, from the 't' tags
+    #[serde(skip)]
+    pub categories: Vec<String>,
+}
+
+impl Product {
+    /// The address of this product, as published by `merchant`
+    pub fn address(&self, merchant: PublicKey) -> NAddr {
+        NAddr {
+            d: self.id.clone(),
+            relays: vec![],
+            kind: EventKind::CreateUpdateProduct,
+            author: merchant,
+        }
+    }
+
+    /// The cost of shipping this product to the shipping zone `zone_id` of `stall`:
+    /// the zone's base cost plus the product's extra cost. None if the stall does
+    /// not ship to that zone.
+    pub fn shipping_cost(&self, stall: &Stall, zone_id: &str) -> Option<f64> {
+        let zone = stall.shipping.iter().find(|z| z.id == zone_id)?;
+        let extra = self
+            .shipping
+            .iter()
+            .find(|s| s.id == zone_id)
+            .map(|s| s.cost)
+            .unwrap_or(0.0);
+        Some(zone.cost + extra)
+    }
+
+    /// Parse a kind-30018 CreateUpdateProduct event
+    pub fn from_event(event: &Event) -> Result<Product, Error> {
+        if event.kind != EventKind::CreateUpdateProduct {
+            return Err(Error::WrongEventKind);
+        }
+        let mut product: Product = serde_json::from_str(&event.content)?;
+        product.categories = event
+            .tags
+            .iter()
+            .filter(|t| t.tagname() == "t")
+            .map(|t| t.value().to_owned())
+            .collect();
+        Ok(product)
+    }
+
+    /// Create the kind-30018 CreateUpdateProduct PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        let mut tags = vec![Tag::new(&["d", &self.id])];
+        for category in self.categories.iter() {
+            tags.push(Tag::new(&["t", category]));
+        }
+
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::CreateUpdateProduct,
+            tags,
+            content: serde_json::to_string(self)?,
+        })
+    }
+}
+
+/// A NIP-15 product sold by auction (kind 30020), the JSON content of a
+/// ProductSoldAuction event
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Auction {
+    /// The auction id, which is also the 'd' tag
+    pub id: String,
+
+    /// The stall the product is sold from
+    pub stall_id: String,
+
+    /// The product name
+    pub name: String,
+
+    /// A description of the product
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Image urls
+    #[serde(default)]
+    pub images: Vec<String>,
+
+    /// The lowest bid allowed, in satoshis
+    pub starting_bid: u64,
+
+    /// When bidding starts. If None, bidding starts when the auction is published.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_date: Option<Unixtime>,
+
+    /// How long bidding lasts, in seconds, before any extensions
+    pub duration: u64,
+
+    /// Specifications, as (key, value) pairs
+    #[serde(default)]
+    pub specs: Vec<(String, String)>,
+
+    /// Extra shipping costs per shipping zone
+    #[serde(default)]
+    pub shipping: Vec<ProductShipping>,
+}
+
+impl Auction {
+    /// Parse a kind-30020 ProductSoldAuction event
+    pub fn from_event(event: &Event) -> Result<Auction, Error> {
+        if event.kind != EventKind::ProductSoldAuction {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(serde_json::from_str(&event.content)?)
+    }
+
+    /// Create the kind-30020 ProductSoldAuction PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::ProductSoldAuction,
+            tags: vec![Tag::new(&["d", &self.id])],
+            content: serde_json::to_string(self)?,
+        })
+    }
+
+    /// Determine the winning bid of the auction `auction_event`.
+    ///
+    /// Only confirmations from the merchant (the author of the auction event) count.
+    /// Bidding ends after the auction's duration plus any extensions given in
+    /// confirmations. A bid counts if it is for this auction, is at least the
+    /// starting bid, was made while bidding was open, and the merchant's latest
+    /// confirmation of it accepted it. If the merchant has declared a winner, that
+    /// bid wins. Otherwise the highest bid wins, and then the earliest.
+    ///
+    /// Signatures are not checked here.
+    pub fn winning_bid<'a>(
+        auction_event: &Event,
+        bids: &'a [Event],
+        confirmations: &[Event],
+    ) -> Option<&'a Event> {
+        let auction = Auction::from_event(auction_event).ok()?;
+        let merchant = auction_event.pubkey;
+
+        let mut confirmations: Vec<(&Event, AuctionBidConfirmation)> = confirmations
+            .iter()
+            .filter(|e| e.pubkey == merchant)
+            .filter_map(|e| Some((e, AuctionBidConfirmation::from_event(e).ok()?)))
+            .filter(|(_, c)| c.auction == auction_event.id)
+            .collect();
+        confirmations
+            .sort_by(|(a, _), (b, _)| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+
+        // Saturate rather than overflow on absurd durations
+        let extensions: u64 = confirmations
+            .iter()
+            .filter_map(|(_, c)| c.duration_extended)
+            .fold(0, u64::saturating_add);
+        let duration = auction.duration.saturating_add(extensions);
+        let start = auction.start_date.unwrap_or(auction_event.created_at);
+        let end = Unixtime(
+            start
+                .0
+                .saturating_add(i64::try_from(duration).unwrap_or(i64::MAX)),
+        );
+
+        // The status of a bid, from the merchant's latest confirmation of it
+        let status = |bid: &Event| {
+            confirmations
+                .iter()
+                .rev()
+                .find(|(_, c)| c.bid == bid.id)
+                .map(|(_, c)| c.status)
+        };
+
+        let mut best: Option<(&'a Event, u64)> = None;
+        for event in bids.iter() {
+            let Ok(bid) = AuctionBid::from_event(event) else {
+                continue;
+            };
+            if bid.auction != auction_event.id
+                || bid.amount < auction.starting_bid
+                || event.created_at < start
+                || event.created_at > end
+            {
+                continue;
+            }
+            match status(event) {
+                Some(AuctionBidStatus::Winner) => return Some(event),
+                Some(AuctionBidStatus::Accepted) => {}
+                _ => continue,
+            }
+            let better = match best {
+                Some((current, amount)) => {
+                    (bid.amount, std::cmp::Reverse((event.created_at, event.id)))
+                        > (amount, std::cmp::Reverse((current.created_at, current.id)))
+                }
+                None => true,
+            };
+            if better {
+                best = Some((event, bid.amount));
+            }
+        }
+        best.map(|(event, _)| event)
+    }
+}
+
+/// A NIP-15 bid on an auction (kind 1021)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AuctionBid {
+    /// The auction event
+    pub auction: Id,
+
+    /// The bid, in satoshis
+    pub amount: u64,
+}
+
+impl AuctionBid {
+    /// Parse a kind-1021 Bid event
+    pub fn from_event(event: &Event) -> Result<AuctionBid, Error> {
+        if event.kind != EventKind::Bid {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(AuctionBid {
+            auction: first_event_reference(event).ok_or(Error::TagMismatch)?,
+            amount: event.content.trim().parse()?,
+        })
+    }
+
+    /// Create the kind-1021 Bid PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::Bid,
+            tags: vec![Tag::new(&["e", &self.auction.as_hex_string()])],
+            content: self.amount.to_string(),
+        }
+    }
+}
+
+/// The merchant's decision on a bid
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuctionBidStatus {
+    /// The bid is accepted
+    Accepted,
+
+    /// The bid is rejected
+    Rejected,
+
+    /// The bid is not yet decided
+    Pending,
+
+    /// The bid won the auction
+    Winner,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BidConfirmationContent {
+    status: AuctionBidStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_extended: Option<u64>,
+}
+
+/// A NIP-15 merchant's confirmation of a bid (kind 1022)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuctionBidConfirmation {
+    /// The bid event
+    pub bid: Id,
+
+    /// The auction event
+    pub auction: Id,
+
+    /// The decision
+    pub status: AuctionBidStatus,
+
+    /// A message to the bidder
+    pub message: Option<String>,
+
+    /// How many seconds the auction is extended by
+    pub duration_extended: Option<u64>,
+}
+
+impl AuctionBidConfirmation {
+    /// Create a new confirmation of a bid
+    pub fn new(bid: &Event, auction: Id, status: AuctionBidStatus) -> AuctionBidConfirmation {
+        AuctionBidConfirmation {
+            bid: bid.id,
+            auction,
+            status,
+            message: None,
+            duration_extended: None,
+        }
+    }
+
+    /// Parse a kind-1022 BidConfirmation event. The first 'e' tag is the bid, and
+    /// the second the auction.
+    pub fn from_event(event: &Event) -> Result<AuctionBidConfirmation, Error> {
+        if event.kind != EventKind::BidConfirmation {
+            return Err(Error::WrongEventKind);
+        }
+
+        let ids: Vec<Id> = event
+            .tags
+            .iter()
+            .filter_map(|tag| match tag.parse() {
+                Ok(ParsedTag::Event { id, .. }) => Some(id),
+                _ => None,
+            })
+            .take(2)
+            .collect();
+        if ids.len() < 2 {
+            return Err(Error::TagMismatch);
+        }
+        let content: BidConfirmationContent = serde_json::from_str(&event.content)?;
+
+        Ok(AuctionBidConfirmation {
+            bid: ids[0],
+            auction: ids[1],
+            status: content.status,
+            message: content.message,
+            duration_extended: content.duration_extended,
+        })
+    }
+
+    /// Create the kind-1022 BidConfirmation PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> Result<PreEvent, Error> {
+        let content = BidConfirmationContent {
+            status: self.status,
+            message: self.message.clone(),
+            duration_extended: self.duration_extended,
+        };
+
+        Ok(PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::BidConfirmation,
+            tags: vec![
+                Tag::new(&["e", &self.bid.as_hex_string()]),
+                Tag::new(&["e", &self.auction.as_hex_string()]),
+            ],
+            content: serde_json::to_string(&content)?,
+        })
+    }
+}
+
+/// How to reach a customer
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderContact {
+    /// Their nostr public key, in hex
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nostr: Option<String>,
+
+    /// Their phone number
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phone: Option<String>,
+
+    /// Their email address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// A product and how many of it were ordered
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderItem {
+    /// The product id
+    pub product_id: String,
+
+    /// How many
+    pub quantity: u64,
+}
+
+/// A NIP-15 customer order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomerOrder {
+    /// The order id
+    pub id: String,
+
+    /// The customer's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Where to ship the order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+
+    /// A message to the merchant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// How to reach the customer
+    #[serde(default)]
+    pub contact: OrderContact,
+
+    /// What was ordered
+    pub items: Vec<OrderItem>,
+
+    /// The shipping zone id
+    pub shipping_id: String,
+}
+
+/// A way to pay for an order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentOption {
+    /// The kind of payment ("url", "btc", "ln" or "lnurl")
+    #[serde(rename = "type")]
+    pub payment_type: String,
+
+    /// Where or how to pay
+    pub link: String,
+}
+
+/// A NIP-15 merchant's request for payment of an order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    /// The order id
+    pub id: String,
+
+    /// A message to the customer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Ways to pay
+    pub payment_options: Vec<PaymentOption>,
+}
+
+/// A NIP-15 merchant's update on the status of an order
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderStatus {
+    /// The order id
+    pub id: String,
+
+    /// A message to the customer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Whether the order has been paid for
+    pub paid: bool,
+
+    /// Whether the order has been shipped
+    pub shipped: bool,
+}
+
+/// A NIP-15 message between a customer and a merchant, sent as an encrypted
+/// direct message (kind 4)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarketplaceMessage {
+    /// A customer's order (type 0)
+    Order(CustomerOrder),
+
+    /// A merchant's request for payment (type 1)
+    PaymentRequest(PaymentRequest),
+
+    /// A merchant's update on an order (type 2)
+    OrderStatus(OrderStatus),
+}
+
+impl MarketplaceMessage {
+    /// The message type number
+    pub fn message_type(&self) -> u64 {
+        match self {
+            MarketplaceMessage::Order(_) => 0,
+            MarketplaceMessage::PaymentRequest(_) => 1,
+            MarketplaceMessage::OrderStatus(_) => 2,
+        }
+    }
+
+    /// Parse a message from its JSON
+    pub fn from_json(json: &str) -> Result<MarketplaceMessage, Error> {
+        let value: serde_json::Value = serde_json::from_str(json)?;
+        match value.get("type").and_then(|t| t.as_u64()) {
+            Some(0) => Ok(MarketplaceMessage::Order(serde_json::from_value(value)?)),
+            Some(1) => Ok(MarketplaceMessage::PaymentRequest(serde_json::from_value(
+                value,
+            )?)),
+            Some(2) => Ok(MarketplaceMessage::OrderStatus(serde_json::from_value(
+                value,
+            )?)),
+            _ => Err(Error::Marketplace("Unknown message type".to_owned())),
+        }
+    }
+
+    /// The message as JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        let mut value = match self {
+            MarketplaceMessage::Order(order) => serde_json::to_value(order)?,
+            MarketplaceMessage::PaymentRequest(request) => serde_json::to_value(request)?,
+            MarketplaceMessage::OrderStatus(status) => serde_json::to_value(status)?,
+        };
+        if let Some(object) = value.as_object_mut() {
+            let _ = object.insert("type".to_owned(), self.message_type().into());
+        }
+        Ok(serde_json::to_string(&value)?)
+    }
+
+    /// Decrypt and parse a kind-4 EncryptedDirectMessage event
+    pub fn from_event<S>(signer: &S, event: &Event) -> Result<MarketplaceMessage, Error>
+    where
+        S: Signer,
+    {
+        if event.kind != EventKind::EncryptedDirectMessage {
+            return Err(Error::WrongEventKind);
+        }
+        MarketplaceMessage::from_json(&signer.decrypt_event_contents(event)?)
+    }
+
+    /// Create the kind-4 EncryptedDirectMessage PreEvent, encrypted to `recipient`
+    pub fn to_pre_event<S>(&self, signer: &S, recipient: PublicKey) -> Result<PreEvent, Error>
+    where
+        S: Signer,
+    {
+        let content = signer.encrypt(
+            &recipient,
+            &self.to_json()?,
+            ContentEncryptionAlgorithm::Nip04,
+        )?;
+
+        Ok(PreEvent {
+            pubkey: signer.public_key(),
+            created_at: Unixtime::now(),
+            kind: EventKind::EncryptedDirectMessage,
+            tags: vec![Tag::new(&["p", &recipient.as_hex_string()])],
+            content,
+        })
+    }
+}
+
+fn first_event_reference(event: &Event) -> Option<Id> {
+    event.tags.iter().find_map(|tag| match tag.parse() {
+        Ok(ParsedTag::Event { id, .. }) => Some(id),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::PrivateKey;
+
+    #[test]
+    fn test_product_and_order() {
+        let merchant = PrivateKey::generate();
+        let customer = PrivateKey::generate();
+
+        let stall = Stall {
+            id: "stall1".to_owned(),
+            name: "Hats".to_owned(),
+            currency: "USD".to_owned(),
+            shipping: vec![ShippingZone {
+                id: "world".to_owned(),
+                name: Some("Worldwide".to_owned()),
+                cost: 10.0,
+                regions: vec!["Everywhere".to_owned()],
+            }],
+            ..Default::default()
+        };
+        let event = merchant
+            .sign_event(stall.to_pre_event(merchant.public_key()).unwrap())
+            .unwrap();
+        assert_eq!(Stall::from_event(&event).unwrap(), stall);
+
+        let product = Product {
+            id: "hat1".to_owned(),
+            stall_id: "stall1".to_owned(),
+            name: "Sombrero".to_owned(),
+            currency: "USD".to_owned(),
+            price: 25.5,
+            quantity: Some(3),
+            specs: vec![("size".to_owned(), "L".to_owned())],
+            shipping: vec![ProductShipping {
+                id: "world".to_owned(),
+                cost: 2.5,
+            }],
+            categories: vec!["hats".to_owned()],
+            ..Default::default()
+        };
+        let event = merchant
+            .sign_event(product.to_pre_event(merchant.public_key()).unwrap())
+            .unwrap();
+        assert_eq!(Product::from_event(&event).unwrap(), product);
+        assert_eq!(product.shipping_cost(&stall, "world"), Some(12.5));
+        assert_eq!(product.shipping_cost(&stall, "moon"), None);
+
+        let order = MarketplaceMessage::Order(CustomerOrder {
+            id: "order1".to_owned(),
+            items: vec![OrderItem {
+                product_id: "hat1".to_owned(),
+                quantity: 1,
+            }],
+            shipping_id: "world".to_owned(),
+            ..Default::default()
+        });
+        assert!(order.to_json().unwrap().contains(r#""type":0"#));
+        let event = customer
+            .sign_event(
+                order
+                    .to_pre_event(&customer, merchant.public_key())
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            MarketplaceMessage::from_event(&merchant, &event).unwrap(),
+            order
+        );
+    }
+
+    #[test]
+    fn test_winning_bid() {
+        let merchant = PrivateKey::generate();
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+
+        let auction = Auction {
+            id: "auction1".to_owned(),
+            stall_id: "stall1".to_owned(),
+            name: "Painting".to_owned(),
+            starting_bid: 1000,
+            start_date: Some(Unixtime(10_000)),
+            duration: 3600,
+            ..Default::default()
+        };
+        let auction_event = merchant
+            .sign_event(auction.to_pre_event(merchant.public_key()).unwrap())
+            .unwrap();
+
+        let bid = |key: &PrivateKey, amount: u64, at: i64| {
+            let mut pre_event = AuctionBid {
+                auction: auction_event.id,
+                amount,
+            }
+            .to_pre_event(key.public_key());
+            pre_event.created_at = Unixtime(at);
+            key.sign_event(pre_event).unwrap()
+        };
+        let confirm = |bid: &Event, status: AuctionBidStatus, extended: Option<u64>, at: i64| {
+            let mut confirmation = AuctionBidConfirmation::new(bid, auction_event.id, status);
+            confirmation.duration_extended = extended;
+            let mut pre_event = confirmation.to_pre_event(merchant.public_key()).unwrap();
+            pre_event.created_at = Unixtime(at);
+            merchant.sign_event(pre_event).unwrap()
+        };
+
+        let bids = vec![
+            bid(&alice, 2000, 11_000),
+            bid(&bob, 3000, 12_000),
+            // Too late, unless the auction is extended
+            bid(&alice, 4000, 14_000),
+            // Too low
+            bid(&bob, 500, 11_500),
+        ];
+        let mut confirmations = vec![
+            confirm(&bids[0], AuctionBidStatus::Accepted, None, 11_000),
+            confirm(&bids[1], AuctionBidStatus::Accepted, None, 12_000),
+            confirm(&bids[2], AuctionBidStatus::Accepted, None, 14_000),
+            confirm(&bids[3], AuctionBidStatus::Accepted, None, 11_500),
+        ];
+        assert_eq!(
+            Auction::winning_bid(&auction_event, &bids, &confirmations),
+            Some(&bids[1])
+        );
+
+        // The auction is extended, so the late bid counts
+        confirmations[1] = confirm(&bids[1], AuctionBidStatus::Accepted, Some(3600), 12_000);
+        assert_eq!(
+            Auction::winning_bid(&auction_event, &bids, &confirmations),
+            Some(&bids[2])
+        );
+
+        // Huge extensions don't overflow
+        confirmations[0] = confirm(&bids[0], AuctionBidStatus::Accepted, Some(u64::MAX), 11_000);
+        confirmations[1] = confirm(&bids[1], AuctionBidStatus::Accepted, Some(u64::MAX), 12_000);
+        assert_eq!(
+            Auction::winning_bid(&auction_event, &bids, &confirmations),
+            Some(&bids[2])
+        );
+
+        // The merchant declares a winner
+        confirmations.push(confirm(&bids[0], AuctionBidStatus::Winner, None, 20_000));
+        assert_eq!(
+            Auction::winning_bid(&auction_event, &bids, &confirmations),
+            Some(&bids[0])
+        );
+    }
+}
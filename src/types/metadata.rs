@@ -7,3 +7,51 @@ use crate::versioned::metadata1::MetadataV1;
 /// events to fail. We treat these in our get() function the same as if the key
 /// did not exist.
 pub type Metadata = MetadataV1;
+
+/// A NIP-24 birthday. Any part may be omitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Birthday {
+    /// Year
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>,
+
+    /// Month, 1-12
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub month: Option<u8>,
+
+    /// Day of the month, 1-31
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub day: Option<u8>,
+}
+
+impl Birthday {
+    /// Whether the month and day are in range. The day is checked against the
+    /// length of the month; February 29 is allowed unless the year is known and
+    /// is not a leap year.
+    pub fn is_valid(&self) -> bool {
+        if let Some(m) = self.month {
+            if !(1..=12).contains(&m) {
+                return false;
+            }
+        }
+        let max_day = match self.month {
+            Some(4) | Some(6) | Some(9) | Some(11) => 30,
+            Some(2) => match self.year {
+                Some(y) if !(y % 4 == 0 && (y % 100 != 0 || y % 400 == 0)) => 28,
+                _ => 29,
+            },
+            _ => 31,
+        };
+        self.day.map(|d| (1..=max_day).contains(&d)).unwrap_or(true)
+    }
+}
+
+/// A problem found by `MetadataV2::validate()`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetadataIssue {
+    /// The metadata field
+    pub field: String,
+
+    /// What is wrong with it
+    pub problem: String,
+}
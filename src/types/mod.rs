@@ -1,12 +1,42 @@
+mod article;
+pub use article::Article;
+
+mod badge;
+pub use badge::{BadgeAward, BadgeDefinition, BadgeImage, ProfileBadge, ProfileBadges};
+
+mod blossom;
+pub use blossom::{BlobDescriptor, BlossomAuth, BlossomServerList, BlossomVerb};
+
+mod calendar;
+pub use calendar::{
+    Calendar, CalendarDate, CalendarEvent, CalendarEventRsvp, CalendarEventTime,
+    CalendarParticipant, FreeBusy, RsvpStatus,
+};
+
+mod channel;
+pub use channel::{Channel, ChannelHide, ChannelInfo, ChannelMessage, ChannelMute, ChannelState};
+
+mod classified_listing;
+pub use classified_listing::{ClassifiedListing, ListingPrice, ListingStatus};
+
 mod client_message;
 pub use client_message::ClientMessage;
 
+mod community;
+pub use community::{CommunityDefinition, CommunityPost, CommunityPostApproval, CommunityRelay};
+
+mod contact_list;
+pub use contact_list::{Contact, ContactList, ContactListDiff, ContactListMerge};
+
 mod content;
-pub use content::{ContentSegment, ShatteredContent, Span};
+pub use content::{ContentSegment, MarkdownSegment, ShatteredContent, ShatteredMarkdown, Span};
 
 mod delegation;
 pub use delegation::{DelegationConditions, EventDelegation};
 
+mod dvm;
+pub use dvm::{JobFeedback, JobInput, JobInputType, JobRequest, JobResult, JobStatus};
+
 mod event;
 pub use event::{Event, PreEvent, Rumor, ZapData};
 
@@ -22,20 +52,49 @@ pub use file_metadata::FileMetadata;
 mod filter;
 pub use filter::Filter;
 
+mod git;
+pub use git::{
+    GitCommitter, GitPatch, GitRepository, GitRepositoryState, GitStatus, GitStatusUpdate,
+};
+
+mod group;
+pub use group::{
+    GroupAction, GroupList, GroupMetadata, GroupMetadataEdit, GroupModeration, GroupState,
+};
+
+mod highlight;
+pub use highlight::{Highlight, HighlightAuthor, HighlightSource};
+
+mod http_auth;
+pub use http_auth::HttpAuth;
+
 mod id;
 pub use id::{Id, IdHex};
 
 mod identity;
 pub use identity::Identity;
 
+mod identity_platform;
+pub use identity_platform::IdentityPlatform;
+
 mod key_signer;
 pub use key_signer::KeySigner;
 
 mod hll8;
 pub use hll8::Hll8;
 
+mod live_activity;
+pub use live_activity::{LiveActivity, LiveActivityStatus, LiveChatMessage, LiveParticipant};
+
+mod marketplace;
+pub use marketplace::{
+    Auction, AuctionBid, AuctionBidConfirmation, AuctionBidStatus, CustomerOrder,
+    MarketplaceMessage, OrderContact, OrderItem, OrderStatus, PaymentOption, PaymentRequest,
+    Product, ProductShipping, ShippingZone, Stall,
+};
+
 mod metadata;
-pub use metadata::Metadata;
+pub use metadata::{Birthday, Metadata, MetadataIssue};
 
 mod naddr;
 pub use naddr::NAddr;
@@ -44,14 +103,33 @@ mod nevent;
 pub use nevent::NEvent;
 
 mod nip05;
-pub use nip05::Nip05;
+pub use nip05::{Nip05, Nip05Identifier};
+
+mod nip96;
+pub use nip96::{
+    Nip96DeleteResponse, Nip96ListResponse, Nip96Nip94Event, Nip96Plan, Nip96ServerInfo,
+    Nip96Status, Nip96UploadResponse,
+};
+
+mod nostr_list;
+pub use nostr_list::NostrList;
 
 mod nostr_url;
 pub use nostr_url::{find_nostr_bech32_pos, find_nostr_url_pos, NostrBech32, NostrUrl};
 
+mod nwc;
+pub use nwc::{
+    NwcClient, NwcEncryption, NwcError, NwcErrorCode, NwcInfo, NwcMultiPayInvoiceItem,
+    NwcNotification, NwcRequest, NwcResponse, NwcResult, NwcTransaction, NwcTransactionType,
+    NwcUri, NwcWalletInfo,
+};
+
 mod pay_request_data;
 pub use pay_request_data::PayRequestData;
 
+mod poll;
+pub use poll::{Poll, PollOption, PollResponse, PollType};
+
 mod private_key;
 pub use private_key::{ContentEncryptionAlgorithm, EncryptedPrivateKey, KeySecurity, PrivateKey};
 
@@ -72,6 +150,9 @@ pub use relay_list::{RelayList, RelayListUsage};
 mod relay_message;
 pub use relay_message::{CountResult, RelayMessage, Why};
 
+mod relay_planner;
+pub use relay_planner::{RelayPlanner, RelayReadPlan};
+
 mod relay_usage;
 pub use relay_usage::{RelayUsage, RelayUsageSet};
 
@@ -99,6 +180,15 @@ pub use unixtime::Unixtime;
 mod url;
 pub use self::url::{RelayOrigin, RelayUrl, UncheckedUrl, Url};
 
+mod user_status;
+pub use user_status::{UserStatus, UserStatusType};
+
+mod wiki;
+pub use wiki::{normalize_wiki_topic, WikiArticle, WikiMergeRequest, WikiRelation};
+
+mod zap_split;
+pub use zap_split::{ZapSplit, ZapSplitPlan};
+
 #[cfg(test)]
 mod test {
     use crate::*;
@@ -1,5 +1,113 @@
 use crate::versioned::nip05::Nip05V1;
+use crate::{Error, Url};
+use std::fmt;
 
 /// The content of a webserver's /.well-known/nostr.json file used in NIP-05 and NIP-35
 /// This allows lookup and verification of a nostr user via a `user@domain` style identifier.
 pub type Nip05 = Nip05V1;
+
+/// A NIP-05 `user@domain` identifier
+///
+/// The local part is stored lowercase. `_@domain` (or just `domain`) is the root
+/// identifier of the domain.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Nip05Identifier {
+    /// The local part, before the '@'
+    pub local: String,
+
+    /// The domain, after the '@'
+    pub domain: String,
+}
+
+impl Nip05Identifier {
+    /// Parse and validate an identifier.
+    ///
+    /// The local part may only contain `a-z0-9-_.` (case-insensitive). A bare
+    /// domain is taken as `_@domain`.
+    pub fn try_from_str(s: &str) -> Result<Nip05Identifier, Error> {
+        let s = s.trim();
+        let (local, domain) = match s.rsplit_once('@') {
+            Some((local, domain)) => (local.to_lowercase(), domain.to_lowercase()),
+            None => ("_".to_owned(), s.to_lowercase()),
+        };
+
+        if local.is_empty()
+            || !local
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "-_.".contains(c))
+        {
+            return Err(Error::InvalidNip05(s.to_owned()));
+        }
+
+        let valid_label = |label: &str| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        if domain.len() > 253 || !domain.contains('.') || !domain.split('.').all(valid_label) {
+            return Err(Error::InvalidNip05(s.to_owned()));
+        }
+
+        Ok(Nip05Identifier { local, domain })
+    }
+
+    /// Whether this is the root identifier of the domain (`_@domain`)
+    pub fn is_root(&self) -> bool {
+        self.local == "_"
+    }
+
+    /// The URL of the nostr.json document to fetch
+    pub fn well_known_url(&self) -> Result<Url, Error> {
+        Url::try_from_str(&format!(
+            "https://{}/.well-known/nostr.json?name={}",
+            self.domain, self.local
+        ))
+    }
+
+    /// How to display the identifier: just the domain for a root identifier
+    pub fn display_name(&self) -> String {
+        if self.is_root() {
+            self.domain.clone()
+        } else {
+            self.to_string()
+        }
+    }
+}
+
+impl fmt::Display for Nip05Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.local, self.domain)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nip05_identifier() {
+        let id = Nip05Identifier::try_from_str("Bob.Smith@Example.COM").unwrap();
+        assert_eq!(id.local, "bob.smith");
+        assert_eq!(id.domain, "example.com");
+        assert_eq!(
+            id.well_known_url().unwrap().as_str(),
+            "https://example.com/.well-known/nostr.json?name=bob.smith"
+        );
+
+        let root = Nip05Identifier::try_from_str("example.com").unwrap();
+        assert!(root.is_root());
+        assert_eq!(
+            root,
+            Nip05Identifier::try_from_str("_@example.com").unwrap()
+        );
+        assert_eq!(root.display_name(), "example.com");
+
+        assert!(Nip05Identifier::try_from_str("bob smith@example.com").is_err());
+        assert!(Nip05Identifier::try_from_str("bob+1@example.com").is_err());
+        assert!(Nip05Identifier::try_from_str("@example.com").is_err());
+        assert!(Nip05Identifier::try_from_str("bob@localhost").is_err());
+        assert!(Nip05Identifier::try_from_str("bob@-bad.com").is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::{Error, FileMetadata, HttpAuth, Signer, Tag, UncheckedUrl, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A NIP-96 server plan
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nip96Plan {
+    /// The plan name
+    #[serde(default)]
+    pub name: String,
+
+    /// Whether uploads require NIP-98 authorization
+    #[serde(default)]
+    pub is_nip98_required: bool,
+
+    /// A landing page for the plan
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<UncheckedUrl>,
+
+    /// The largest file allowed, in bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_byte_size: Option<u64>,
+
+    /// The range of days files are kept, [min, max]. [0, 0] means forever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_expiration: Option<(u64, u64)>,
+
+    /// The transformations offered for each kind of media
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub media_transformations: HashMap<String, Vec<String>>,
+}
+
+/// A NIP-96 server's `/.well-known/nostr/nip96.json` document
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nip96ServerInfo {
+    /// The API endpoint. Uploads are sent here, and deletes to `<api_url>/<sha256>`.
+    #[serde(default)]
+    pub api_url: String,
+
+    /// Where files are downloaded from, if not the api_url
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<UncheckedUrl>,
+
+    /// A server whose nip96.json should be used instead. Only set if api_url is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegated_to_url: Option<UncheckedUrl>,
+
+    /// Other NIPs the server supports
+    #[serde(default)]
+    pub supported_nips: Vec<u32>,
+
+    /// The terms of service
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tos_url: Option<UncheckedUrl>,
+
+    /// Accepted mime types, possibly with wildcards like "audio/*". Empty means all.
+    #[serde(default)]
+    pub content_types: Vec<String>,
+
+    /// The plans, by id
+    #[serde(default)]
+    pub plans: HashMap<String, Nip96Plan>,
+}
+
+impl Nip96ServerInfo {
+    /// The well-known URL of the nip96.json document for a server
+    pub fn well_known_url(server: &Url) -> Result<Url, Error> {
+        let mut url = url::Url::parse(server.as_str())?;
+        url.set_path("/.well-known/nostr/nip96.json");
+        url.set_query(None);
+        Url::try_from_str(url.as_str())
+    }
+
+    /// The API URL. Fails if the server delegates to another server.
+    pub fn api_url(&self) -> Result<Url, Error> {
+        if self.api_url.is_empty() {
+            return Err(Error::Nip96(match &self.delegated_to_url {
+                Some(u) => format!("Server delegates to {}", u.0),
+                None => "Server has no api_url".to_owned(),
+            }));
+        }
+        Url::try_from_str(&self.api_url)
+    }
+
+    /// Whether the server accepts files of this mime type
+    pub fn accepts(&self, mime_type: &str) -> bool {
+        if self.content_types.is_empty() {
+            return true;
+        }
+        let mime_type = mime_type.to_lowercase();
+        self.content_types.iter().any(|ct| {
+            let ct = ct.to_lowercase();
+            match ct.strip_suffix("/*") {
+                Some(prefix) => mime_type.split('/').next() == Some(prefix),
+                None => ct == mime_type,
+            }
+        })
+    }
+
+    /// The HTTP `Authorization` header value for uploading `file`.
+    ///
+    /// The NIP-98 payload is the sha256 hash of the file.
+    pub fn upload_authorization_header<S>(&self, signer: &S, file: &[u8]) -> Result<String, Error>
+    where
+        S: Signer,
+    {
+        HttpAuth::new(self.api_url()?, "POST", Some(file)).create_authorization_header(signer)
+    }
+
+    /// The URL to delete a file from, and the HTTP `Authorization` header value for it
+    pub fn delete_request<S>(&self, signer: &S, sha256: &str) -> Result<(Url, String), Error>
+    where
+        S: Signer,
+    {
+        let url = Url::try_from_str(&format!(
+            "{}/{}",
+            self.api_url()?.as_str().trim_end_matches('/'),
+            sha256
+        ))?;
+        let header =
+            HttpAuth::new(url.clone(), "DELETE", None).create_authorization_header(signer)?;
+        Ok((url, header))
+    }
+}
+
+/// The status of a NIP-96 response
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Nip96Status {
+    /// The request succeeded
+    Success,
+
+    /// The request failed, see the message
+    Error,
+
+    /// The upload is still being processed
+    Processing,
+}
+
+/// The NIP-94 event embedded in NIP-96 responses. It is not signed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nip96Nip94Event {
+    /// The NIP-94 tags
+    #[serde(default)]
+    pub tags: Vec<Tag>,
+
+    /// The content, usually a caption
+    #[serde(default)]
+    pub content: String,
+}
+
+impl Nip96Nip94Event {
+    /// Convert to FileMetadata. Returns None if there is no 'url' tag.
+    pub fn to_file_metadata(&self) -> Option<FileMetadata> {
+        FileMetadata::from_nip94_tags(&self.tags)
+    }
+}
+
+/// A NIP-96 upload response (also used for processing status)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nip96UploadResponse {
+    /// The status
+    pub status: Nip96Status,
+
+    /// A human readable message
+    #[serde(default)]
+    pub message: String,
+
+    /// Where to poll for completion, if processing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processing_url: Option<UncheckedUrl>,
+
+    /// Processing progress, 0 to 100
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u8>,
+
+    /// The uploaded file, once available
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nip94_event: Option<Nip96Nip94Event>,
+}
+
+impl Nip96UploadResponse {
+    /// The uploaded file's metadata.
+    ///
+    /// Returns `Ok(None)` while processing, and an error if the server reported one
+    /// or the response is missing the file.
+    pub fn file_metadata(&self) -> Result<Option<FileMetadata>, Error> {
+        match self.status {
+            Nip96Status::Error => Err(Error::Nip96(self.message.clone())),
+            Nip96Status::Processing if self.nip94_event.is_none() => Ok(None),
+            _ => match self.nip94_event.as_ref().and_then(|e| e.to_file_metadata()) {
+                Some(fm) => Ok(Some(fm)),
+                None => Err(Error::Nip96("Response has no file url".to_owned())),
+            },
+        }
+    }
+}
+
+/// A NIP-96 delete response
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nip96DeleteResponse {
+    /// The status
+    pub status: Nip96Status,
+
+    /// A human readable message
+    #[serde(default)]
+    pub message: String,
+}
+
+impl Nip96DeleteResponse {
+    /// Fail if the server reported an error
+    pub fn check(&self) -> Result<(), Error> {
+        match self.status {
+            Nip96Status::Error => Err(Error::Nip96(self.message.clone())),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A NIP-96 file list response
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nip96ListResponse {
+    /// The number of files in this page
+    #[serde(default)]
+    pub count: u64,
+
+    /// The total number of files
+    #[serde(default)]
+    pub total: u64,
+
+    /// The page number
+    #[serde(default)]
+    pub page: u64,
+
+    /// The files
+    #[serde(default)]
+    pub files: Vec<Nip96Nip94Event>,
+}
+
+impl Nip96ListResponse {
+    /// The metadata of each file that has a url
+    pub fn file_metadata(&self) -> Vec<FileMetadata> {
+        self.files
+            .iter()
+            .filter_map(|f| f.to_file_metadata())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EventKind, PrivateKey};
+
+    #[test]
+    fn test_nip96_server_info() {
+        let json = r#"{
+            "api_url": "https://files.example.com/api/v2/media",
+            "download_url": "https://cdn.example.com",
+            "supported_nips": [60],
+            "tos_url": "https://files.example.com/tos",
+            "content_types": ["image/jpeg", "video/webm", "audio/*"],
+            "plans": {
+                "free": {
+                    "name": "Free Tier",
+                    "is_nip98_required": true,
+                    "url": "https://files.example.com/plans",
+                    "max_byte_size": 10485760,
+                    "file_expiration": [14, 90],
+                    "media_transformations": { "image": ["resizing"] }
+                }
+            }
+        }"#;
+        let info: Nip96ServerInfo = serde_json::from_str(json).unwrap();
+        assert!(info.accepts("audio/ogg"));
+        assert!(!info.accepts("image/png"));
+        assert_eq!(info.plans["free"].file_expiration, Some((14, 90)));
+
+        let private_key = PrivateKey::generate();
+        let file = b"some file";
+        let header = info
+            .upload_authorization_header(&private_key, file)
+            .unwrap();
+        let event = HttpAuth::event_from_header(&header).unwrap();
+        assert_eq!(event.kind, EventKind::HttpAuth);
+        assert_eq!(
+            HttpAuth::verify_event(&event, &info.api_url().unwrap(), "POST", Some(file), 60)
+                .unwrap(),
+            private_key.public_key()
+        );
+
+        let url = Url::try_from_str("https://files.example.com/some/page?q=1").unwrap();
+        assert_eq!(
+            Nip96ServerInfo::well_known_url(&url).unwrap().as_str(),
+            "https://files.example.com/.well-known/nostr/nip96.json"
+        );
+    }
+
+    #[test]
+    fn test_nip96_upload_response() {
+        let json = r#"{
+            "status": "success",
+            "message": "Upload successful.",
+            "nip94_event": {
+                "tags": [
+                    ["url", "https://cdn.example.com/719171db19525d9d08dd69cb716a18158a249b7b3b3ec4bbdec5698dca104b7b.png"],
+                    ["ox", "719171db19525d9d08dd69cb716a18158a249b7b3b3ec4bbdec5698dca104b7b"],
+                    ["x", "543244319525d9d08dd69cb716a18158a249b7b3b3ec4bbde5435543acb34443"],
+                    ["m", "image/png"],
+                    ["dim", "800x600"]
+                ],
+                "content": ""
+            }
+        }"#;
+        let response: Nip96UploadResponse = serde_json::from_str(json).unwrap();
+        let fm = response.file_metadata().unwrap().unwrap();
+        assert_eq!(fm.m.as_deref(), Some("image/png"));
+        assert_eq!(fm.dim, Some((800, 600)));
+
+        let error: Nip96UploadResponse =
+            serde_json::from_str(r#"{"status":"error","message":"File too large"}"#).unwrap();
+        assert!(error.file_metadata().is_err());
+    }
+}
@@ -0,0 +1,346 @@
+use crate::{ContentEncryptionAlgorithm, Error, Event, EventKind, PreEvent, Signer, Tag, Unixtime};
+
+/// A NIP-51 list or set
+///
+/// Public items are the event's tags. Private items are a JSON array of tags,
+/// encrypted to the author's own key in the event content.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NostrList {
+    /// The list kind
+    pub kind: EventKind,
+
+    /// The set identifier ('d' tag). Only used by set kinds (30000-39999).
+    pub d: Option<String>,
+
+    /// The set title
+    pub title: Option<String>,
+
+    /// The set image
+    pub image: Option<String>,
+
+    /// The set description
+    pub description: Option<String>,
+
+    /// The public items
+    pub public: Vec<Tag>,
+
+    /// The private items. These are only available after `decrypt_private()`.
+    pub private: Vec<Tag>,
+
+    // The content of the event this came from, if it has not been decrypted yet
+    encrypted: Option<String>,
+}
+
+impl NostrList {
+    /// Create a new empty list of a NIP-51 standard list kind
+    pub fn new(kind: EventKind) -> Result<NostrList, Error> {
+        if !Self::is_list_kind(kind) || kind.is_parameterized_replaceable() {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(Self::empty(kind, None))
+    }
+
+    /// Create a new empty set of a NIP-51 set kind with the given identifier
+    pub fn new_set(kind: EventKind, d: String) -> Result<NostrList, Error> {
+        if !Self::is_list_kind(kind) || !kind.is_parameterized_replaceable() {
+            return Err(Error::WrongEventKind);
+        }
+        Ok(Self::empty(kind, Some(d)))
+    }
+
+    fn empty(kind: EventKind, d: Option<String>) -> NostrList {
+        NostrList {
+            kind,
+            d,
+            title: None,
+            image: None,
+            description: None,
+            public: Vec::new(),
+            private: Vec::new(),
+            encrypted: None,
+        }
+    }
+
+    // The NIP-51 standard list and set kinds
+    fn is_list_kind(kind: EventKind) -> bool {
+        use EventKind::*;
+        matches!(
+            kind,
+            MuteList
+                | PinList
+                | RelayList
+                | BookmarkList
+                | CommunityList
+                | PublicChatsList
+                | BlockedRelaysList
+                | SearchRelaysList
+                | UserGroups
+                | InterestsList
+                | UserEmojiList
+                | DmRelayList
+                | FollowSets
+                | GenericSets
+                | RelaySets
+                | BookmarkSets
+                | CurationSets
+                | VideoSets
+                | KindMuteSets
+                | InterestSets
+                | EmojiSets
+                | ReleaseArtifactSets
+        )
+    }
+
+    /// Read a list from an event. Private items stay encrypted until
+    /// `decrypt_private()` is called.
+    pub fn from_event(event: &Event) -> Result<NostrList, Error> {
+        if !Self::is_list_kind(event.kind) {
+            return Err(Error::WrongEventKind);
+        }
+
+        let set = event.kind.is_parameterized_replaceable();
+        let mut list = Self::empty(event.kind, None);
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" if set && list.d.is_none() => list.d = Some(tag.value().to_owned()),
+                "title" if set && list.title.is_none() => list.title = Some(tag.value().to_owned()),
+                "image" if set && list.image.is_none() => list.image = Some(tag.value().to_owned()),
+                "description" if set && list.description.is_none() => {
+                    list.description = Some(tag.value().to_owned())
+                }
+                _ => list.public.push(tag.clone()),
+            }
+        }
+        if set && list.d.is_none() {
+            list.d = Some("".to_owned());
+        }
+
+        if !event.content.is_empty() {
+            list.encrypted = Some(event.content.clone());
+        }
+
+        Ok(list)
+    }
+
+    /// Whether private items are still encrypted
+    pub fn has_encrypted_private(&self) -> bool {
+        self.encrypted.is_some()
+    }
+
+    /// Decrypt the private items. The signer must be the author of the list.
+    ///
+    /// Both NIP-44 and legacy NIP-04 content can be read.
+    pub fn decrypt_private<S>(&mut self, signer: &S) -> Result<(), Error>
+    where
+        S: Signer,
+    {
+        if let Some(content) = &self.encrypted {
+            let json = signer.decrypt(&signer.public_key(), content)?;
+            self.private = serde_json::from_str(&json)?;
+            self.encrypted = None;
+        }
+        Ok(())
+    }
+
+    /// Whether the list contains an item matching the tag name and value of `tag`,
+    /// either publicly or privately.
+    ///
+    /// Fails if private items are still encrypted; call `decrypt_private()` first.
+    pub fn contains(&self, tag: &Tag) -> Result<bool, Error> {
+        self.check_decrypted()?;
+        Ok(self.public.iter().any(|t| same_item(t, tag))
+            || self.private.iter().any(|t| same_item(t, tag)))
+    }
+
+    /// Add an item. Returns false (and changes nothing) if the list already
+    /// contains an item with the same tag name and value.
+    ///
+    /// Fails if private items are still encrypted; call `decrypt_private()` first.
+    pub fn add(&mut self, tag: Tag, private: bool) -> Result<bool, Error> {
+        if self.contains(&tag)? {
+            return Ok(false);
+        }
+        if private {
+            self.private.push(tag);
+        } else {
+            self.public.push(tag);
+        }
+        Ok(true)
+    }
+
+    /// Remove every item matching the tag name and value of `tag`. Returns true if
+    /// anything was removed.
+    ///
+    /// Fails if private items are still encrypted; call `decrypt_private()` first.
+    /// Otherwise an encrypted item could not be removed, and would come back when
+    /// the list is published.
+    pub fn remove(&mut self, tag: &Tag) -> Result<bool, Error> {
+        self.check_decrypted()?;
+        let before = self.public.len() + self.private.len();
+        self.public.retain(|t| !same_item(t, tag));
+        self.private.retain(|t| !same_item(t, tag));
+        Ok(before != self.public.len() + self.private.len())
+    }
+
+    fn check_decrypted(&self) -> Result<(), Error> {
+        if self.encrypted.is_some() {
+            return Err(Error::ListEncrypted);
+        }
+        Ok(())
+    }
+
+    /// Convert into a PreEvent, encrypting private items with NIP-44.
+    ///
+    /// The signer must be the author of the list. Any private items that are still
+    /// encrypted are decrypted first so that they are not lost.
+    pub fn to_pre_event<S>(&mut self, signer: &S) -> Result<PreEvent, Error>
+    where
+        S: Signer,
+    {
+        self.decrypt_private(signer)?;
+
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(d) = &self.d {
+            tags.push(Tag::new(&["d", d]));
+            if let Some(title) = &self.title {
+                tags.push(Tag::new(&["title", title]));
+            }
+            if let Some(image) = &self.image {
+                tags.push(Tag::new(&["image", image]));
+            }
+            if let Some(description) = &self.description {
+                tags.push(Tag::new(&["description", description]));
+            }
+        }
+        tags.extend(self.public.iter().cloned());
+
+        let content = if self.private.is_empty() {
+            "".to_owned()
+        } else {
+            let json = serde_json::to_string(&self.private)?;
+            signer.encrypt(
+                &signer.public_key(),
+                &json,
+                ContentEncryptionAlgorithm::Nip44v2,
+            )?
+        };
+
+        Ok(PreEvent {
+            pubkey: signer.public_key(),
+            created_at: Unixtime::now(),
+            kind: self.kind,
+            tags,
+            content,
+        })
+    }
+}
+
+// Items are the same if the tag name and value match; relay hints and
+// other trailing fields do not matter
+fn same_item(a: &Tag, b: &Tag) -> bool {
+    a.tagname() == b.tagname() && a.value() == b.value()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, PublicKey};
+
+    #[test]
+    fn test_nostr_list_round_trip() {
+        let private_key = PrivateKey::generate();
+        let alice = Tag::new(&["p", &PublicKey::mock().as_hex_string()]);
+        let bob = Tag::new(&["p", &PublicKey::mock().as_hex_string()]);
+        let word = Tag::new(&["word", "spam"]);
+
+        let mut list = NostrList::new(EventKind::MuteList).unwrap();
+        assert!(list.add(alice.clone(), false).unwrap());
+        assert!(list.add(bob.clone(), true).unwrap());
+        assert!(list.add(word.clone(), true).unwrap());
+        assert!(!list.add(bob.clone(), false).unwrap());
+        assert!(list.remove(&word).unwrap());
+        assert!(!list.contains(&word).unwrap());
+
+        let pre_event = list.to_pre_event(&private_key).unwrap();
+        assert_eq!(pre_event.tags, vec![alice.clone()]);
+        let event = private_key.sign_event(pre_event).unwrap();
+
+        let mut list2 = NostrList::from_event(&event).unwrap();
+        assert!(list2.has_encrypted_private());
+        assert!(list2.contains(&bob).is_err());
+        list2.decrypt_private(&private_key).unwrap();
+        assert!(list2.contains(&bob).unwrap());
+        assert_eq!(list2, list);
+    }
+
+    #[test]
+    fn test_nostr_list_remove_encrypted() {
+        let private_key = PrivateKey::generate();
+        let word = Tag::new(&["word", "spam"]);
+
+        let mut list = NostrList::new(EventKind::MuteList).unwrap();
+        assert!(list.add(word.clone(), true).unwrap());
+        let event = private_key
+            .sign_event(list.to_pre_event(&private_key).unwrap())
+            .unwrap();
+
+        // Removing a private item that is still encrypted must not silently fail
+        let mut list = NostrList::from_event(&event).unwrap();
+        assert!(list.remove(&word).is_err());
+        assert!(list.add(word.clone(), false).is_err());
+
+        list.decrypt_private(&private_key).unwrap();
+        assert!(list.remove(&word).unwrap());
+        let event = private_key
+            .sign_event(list.to_pre_event(&private_key).unwrap())
+            .unwrap();
+        let mut list = NostrList::from_event(&event).unwrap();
+        list.decrypt_private(&private_key).unwrap();
+        assert!(!list.contains(&word).unwrap());
+        assert!(list.private.is_empty());
+    }
+
+    #[test]
+    fn test_nostr_list_set_and_legacy() {
+        let private_key = PrivateKey::generate();
+        let pubkey = private_key.public_key();
+        let hidden = Tag::new(&["e", &crate::Id::mock().as_hex_string()]);
+
+        // Legacy NIP-04 private content
+        let content = private_key
+            .encrypt(
+                &pubkey,
+                &serde_json::to_string(&vec![hidden.clone()]).unwrap(),
+                ContentEncryptionAlgorithm::Nip04,
+            )
+            .unwrap();
+        let event = private_key
+            .sign_event(PreEvent {
+                pubkey,
+                created_at: Unixtime::mock(),
+                kind: EventKind::GenericSets,
+                tags: vec![
+                    Tag::new(&["d", "reading"]),
+                    Tag::new(&["title", "Reading"]),
+                    Tag::new(&["image", "https://example.com/a.png"]),
+                ],
+                content,
+            })
+            .unwrap();
+
+        let mut list = NostrList::from_event(&event).unwrap();
+        assert_eq!(list.d.as_deref(), Some("reading"));
+        assert_eq!(list.title.as_deref(), Some("Reading"));
+        assert!(list.public.is_empty());
+
+        let pre_event = list.to_pre_event(&private_key).unwrap();
+        assert!(list.contains(&hidden).unwrap());
+        assert_eq!(pre_event.tags[0], Tag::new(&["d", "reading"]));
+        assert_eq!(pre_event.tags.len(), 3);
+
+        assert!(NostrList::new(EventKind::GenericSets).is_err());
+        assert!(NostrList::new_set(EventKind::LongFormContent, "x".to_owned()).is_err());
+        assert!(NostrList::new_set(EventKind::Calendar, "x".to_owned()).is_err());
+        assert!(NostrList::new_set(EventKind::FollowSets, "x".to_owned()).is_ok());
+    }
+}
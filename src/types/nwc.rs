@@ -0,0 +1,933 @@
+use crate::{
+    ContentEncryptionAlgorithm, Error, Event, EventKind, Filter, Id, ParsedTag, PreEvent,
+    PrivateKey, PublicKey, RelayUrl, Signer, Tag, Unixtime,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+const NWC_SCHEME: &str = "nostr+walletconnect";
+
+/// A Nostr Wallet Connect connection URI (NIP-47)
+///
+/// `nostr+walletconnect://<wallet-pubkey>?relay=<relay>&secret=<hex>&lud16=<address>`
+#[derive(Debug)]
+pub struct NwcUri {
+    /// The public key of the wallet service
+    pub wallet_pubkey: PublicKey,
+
+    /// The relays the wallet service listens on
+    pub relays: Vec<RelayUrl>,
+
+    /// The secret the client signs and encrypts with. This key is not the
+    /// user's identity key.
+    pub secret: PrivateKey,
+
+    /// The lightning address of the wallet, if provided
+    pub lud16: Option<String>,
+}
+
+impl NwcUri {
+    /// Parse a `nostr+walletconnect://` URI
+    pub fn try_from_str(s: &str) -> Result<NwcUri, Error> {
+        let url = url::Url::parse(s.trim())?;
+        if url.scheme() != NWC_SCHEME {
+            return Err(Error::WalletConnect(format!(
+                "Wrong URI scheme: {}",
+                url.scheme()
+            )));
+        }
+
+        // The pubkey is the host, though some wallets put it in the path
+        let pubkey_str = match url.host_str() {
+            Some(h) if !h.is_empty() => h.to_owned(),
+            _ => url.path().trim_start_matches('/').to_owned(),
+        };
+        let wallet_pubkey = PublicKey::try_from_hex_string(&pubkey_str, true)?;
+
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret: Option<PrivateKey> = None;
+        let mut lud16: Option<String> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => relays.push(RelayUrl::try_from_str(&value)?),
+                "secret" => secret = Some(PrivateKey::try_from_hex_string(&value)?),
+                "lud16" => lud16 = Some(value.into_owned()),
+                _ => {}
+            }
+        }
+
+        if relays.is_empty() {
+            return Err(Error::WalletConnect("URI has no relay".to_owned()));
+        }
+        let secret = match secret {
+            Some(s) => s,
+            None => return Err(Error::WalletConnect("URI has no secret".to_owned())),
+        };
+
+        Ok(NwcUri {
+            wallet_pubkey,
+            relays,
+            secret,
+            lud16,
+        })
+    }
+
+    /// Export as a `nostr+walletconnect://` URI
+    ///
+    /// This exposes the secret, so it requires `&mut self` like other private key exports.
+    pub fn as_uri_string(&mut self) -> String {
+        let mut output = format!("{}://{}?", NWC_SCHEME, self.wallet_pubkey.as_hex_string());
+        let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+        for relay in self.relays.iter() {
+            let _ = serializer.append_pair("relay", relay.as_str());
+        }
+        let _ = serializer.append_pair("secret", &self.secret.as_hex_string());
+        if let Some(lud16) = &self.lud16 {
+            let _ = serializer.append_pair("lud16", lud16);
+        }
+        output.push_str(&serializer.finish());
+        output
+    }
+}
+
+/// The encryption schemes a wallet service may support (NIP-47)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NwcEncryption {
+    /// NIP-04, the legacy scheme assumed when a wallet does not say
+    Nip04,
+
+    /// NIP-44 version 2
+    Nip44v2,
+}
+
+impl NwcEncryption {
+    /// The name used in 'encryption' tags
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            NwcEncryption::Nip04 => "nip04",
+            NwcEncryption::Nip44v2 => "nip44_v2",
+        }
+    }
+
+    /// Parse the name used in 'encryption' tags
+    pub fn from_str_opt(s: &str) -> Option<NwcEncryption> {
+        match s {
+            "nip04" => Some(NwcEncryption::Nip04),
+            "nip44_v2" => Some(NwcEncryption::Nip44v2),
+            _ => None,
+        }
+    }
+
+    /// The content encryption algorithm for this scheme
+    pub fn algorithm(&self) -> ContentEncryptionAlgorithm {
+        match *self {
+            NwcEncryption::Nip04 => ContentEncryptionAlgorithm::Nip04,
+            NwcEncryption::Nip44v2 => ContentEncryptionAlgorithm::Nip44v2,
+        }
+    }
+
+    /// The event kind that notifications are sent with under this scheme
+    pub fn notification_kind(&self) -> EventKind {
+        match *self {
+            NwcEncryption::Nip04 => EventKind::WalletNotificationNip04,
+            NwcEncryption::Nip44v2 => EventKind::WalletNotification,
+        }
+    }
+}
+
+/// The capabilities a wallet service advertises in its info event (kind 13194)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NwcInfo {
+    /// The methods the wallet service supports
+    pub methods: Vec<String>,
+
+    /// The notification types the wallet service sends
+    pub notifications: Vec<String>,
+
+    /// The encryption schemes the wallet service accepts. Empty if the info
+    /// event does not say, which means NIP-04 only.
+    pub encryptions: Vec<NwcEncryption>,
+}
+
+impl NwcInfo {
+    /// Parse a wallet info event
+    pub fn from_event(event: &Event) -> Result<NwcInfo, Error> {
+        if event.kind != EventKind::WalletInfo {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut info = NwcInfo {
+            methods: event
+                .content
+                .split_whitespace()
+                .map(|s| s.to_owned())
+                .collect(),
+            ..Default::default()
+        };
+
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "notifications" => {
+                    info.notifications = tag
+                        .value()
+                        .split_whitespace()
+                        .map(|s| s.to_owned())
+                        .collect();
+                }
+                "encryption" => {
+                    info.encryptions = tag
+                        .value()
+                        .split_whitespace()
+                        .filter_map(NwcEncryption::from_str_opt)
+                        .collect();
+                }
+                _ => {}
+            }
+        }
+
+        Ok(info)
+    }
+
+    /// Whether the wallet service supports the given method
+    pub fn supports(&self, method: &str) -> bool {
+        self.methods.iter().any(|m| m == method)
+    }
+
+    /// The encryption to use with this wallet service, preferring NIP-44
+    pub fn negotiate_encryption(&self) -> NwcEncryption {
+        if self.encryptions.contains(&NwcEncryption::Nip44v2) {
+            NwcEncryption::Nip44v2
+        } else {
+            NwcEncryption::Nip04
+        }
+    }
+}
+
+/// The direction of a wallet transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NwcTransactionType {
+    /// Money received
+    Incoming,
+
+    /// Money sent
+    Outgoing,
+}
+
+/// A wallet transaction (NIP-47)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NwcTransaction {
+    /// Incoming or outgoing
+    #[serde(rename = "type")]
+    pub transaction_type: NwcTransactionType,
+
+    /// The bolt11 invoice
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<String>,
+
+    /// The invoice description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The invoice description hash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_hash: Option<String>,
+
+    /// The payment preimage, once paid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preimage: Option<String>,
+
+    /// The payment hash
+    pub payment_hash: String,
+
+    /// The amount in millisatoshis
+    pub amount: u64,
+
+    /// The fees paid in millisatoshis
+    #[serde(default)]
+    pub fees_paid: u64,
+
+    /// When the invoice was created
+    pub created_at: Unixtime,
+
+    /// When the invoice expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Unixtime>,
+
+    /// When the payment settled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settled_at: Option<Unixtime>,
+
+    /// Additional wallet-specific data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+}
+
+/// One invoice within a `multi_pay_invoice` request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NwcMultiPayInvoiceItem {
+    /// An id used to match the response, which is returned in its 'd' tag.
+    /// If not given, the payment hash of the invoice is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    /// The bolt11 invoice
+    pub invoice: String,
+
+    /// The amount in millisatoshis, for invoices without an amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<u64>,
+}
+
+/// A request to a wallet service (NIP-47)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum NwcRequest {
+    /// Pay a bolt11 invoice
+    PayInvoice {
+        /// The bolt11 invoice
+        invoice: String,
+
+        /// The amount in millisatoshis, for invoices without an amount
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        amount: Option<u64>,
+    },
+
+    /// Pay several bolt11 invoices. One response comes back per invoice.
+    MultiPayInvoice {
+        /// The invoices
+        invoices: Vec<NwcMultiPayInvoiceItem>,
+    },
+
+    /// Create an invoice
+    MakeInvoice {
+        /// The amount in millisatoshis
+        amount: u64,
+
+        /// The invoice description
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+
+        /// The invoice description hash
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        description_hash: Option<String>,
+
+        /// Seconds until the invoice expires
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expiry: Option<u64>,
+    },
+
+    /// Look up an invoice by payment hash or by invoice
+    LookupInvoice {
+        /// The payment hash
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        payment_hash: Option<String>,
+
+        /// The bolt11 invoice
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        invoice: Option<String>,
+    },
+
+    /// List invoices and payments
+    ListTransactions {
+        /// Only transactions created at or after this time
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        from: Option<Unixtime>,
+
+        /// Only transactions created at or before this time
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        until: Option<Unixtime>,
+
+        /// The maximum number of transactions
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        limit: Option<u64>,
+
+        /// How many transactions to skip
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+
+        /// Whether to include unpaid invoices
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unpaid: Option<bool>,
+
+        /// Only transactions in this direction
+        #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+        transaction_type: Option<NwcTransactionType>,
+    },
+
+    /// Get the wallet balance
+    GetBalance {},
+
+    /// Get information about the wallet and connection
+    GetInfo {},
+}
+
+impl NwcRequest {
+    /// The method name
+    pub fn method(&self) -> &'static str {
+        match *self {
+            NwcRequest::PayInvoice { .. } => "pay_invoice",
+            NwcRequest::MultiPayInvoice { .. } => "multi_pay_invoice",
+            NwcRequest::MakeInvoice { .. } => "make_invoice",
+            NwcRequest::LookupInvoice { .. } => "lookup_invoice",
+            NwcRequest::ListTransactions { .. } => "list_transactions",
+            NwcRequest::GetBalance {} => "get_balance",
+            NwcRequest::GetInfo {} => "get_info",
+        }
+    }
+}
+
+/// NIP-47 error codes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum NwcErrorCode {
+    /// The client is sending commands too fast
+    RateLimited,
+
+    /// The command is not known or is intentionally not implemented
+    NotImplemented,
+
+    /// The wallet does not have enough funds
+    InsufficientBalance,
+
+    /// The wallet has exceeded its spending quota
+    QuotaExceeded,
+
+    /// This public key is not allowed to do this operation
+    Restricted,
+
+    /// This public key has no wallet connected
+    Unauthorized,
+
+    /// An internal error
+    Internal,
+
+    /// The payment failed
+    PaymentFailed,
+
+    /// The invoice could not be found
+    NotFound,
+
+    /// The encryption scheme is not supported
+    UnsupportedEncryption,
+
+    /// Any other error, including codes not known to this library
+    #[serde(other)]
+    Other,
+}
+
+/// An error returned by a wallet service (NIP-47)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NwcError {
+    /// The error code
+    pub code: NwcErrorCode,
+
+    /// A human readable message
+    #[serde(default)]
+    pub message: String,
+}
+
+/// Information about a wallet, from a `get_info` response
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NwcWalletInfo {
+    /// The node alias
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+
+    /// The node color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+
+    /// The node public key (hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+
+    /// The network, e.g. "mainnet"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+
+    /// The current block height
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<u64>,
+
+    /// The current block hash
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_hash: Option<String>,
+
+    /// The methods this connection may use
+    #[serde(default)]
+    pub methods: Vec<String>,
+
+    /// The notifications this connection may receive
+    #[serde(default)]
+    pub notifications: Vec<String>,
+}
+
+/// The result of a successful wallet request
+#[derive(Clone, Debug, PartialEq)]
+pub enum NwcResult {
+    /// An invoice was paid (`pay_invoice` or one invoice of `multi_pay_invoice`)
+    Payment {
+        /// The payment preimage
+        preimage: String,
+
+        /// The fees paid in millisatoshis
+        fees_paid: Option<u64>,
+    },
+
+    /// The created invoice
+    MakeInvoice(NwcTransaction),
+
+    /// The invoice that was looked up
+    LookupInvoice(NwcTransaction),
+
+    /// The matching transactions
+    ListTransactions(Vec<NwcTransaction>),
+
+    /// The wallet balance in millisatoshis
+    GetBalance(u64),
+
+    /// Information about the wallet
+    GetInfo(NwcWalletInfo),
+}
+
+/// A response from a wallet service (NIP-47)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NwcResponse {
+    /// The method this is a response to
+    pub result_type: String,
+
+    /// The error, if the request failed
+    #[serde(default)]
+    pub error: Option<NwcError>,
+
+    /// The untyped result, if the request succeeded. See `result()`.
+    #[serde(default)]
+    pub result: Option<Value>,
+}
+
+impl NwcResponse {
+    /// Create a successful response
+    pub fn from_result(result: &NwcResult, result_type: &str) -> Result<NwcResponse, Error> {
+        let value = match result {
+            NwcResult::Payment {
+                preimage,
+                fees_paid,
+            } => {
+                let mut map = serde_json::Map::new();
+                let _ = map.insert("preimage".to_owned(), Value::String(preimage.clone()));
+                if let Some(fees) = fees_paid {
+                    let _ = map.insert("fees_paid".to_owned(), Value::from(*fees));
+                }
+                Value::Object(map)
+            }
+            NwcResult::MakeInvoice(tx) | NwcResult::LookupInvoice(tx) => serde_json::to_value(tx)?,
+            NwcResult::ListTransactions(txs) => {
+                serde_json::json!({ "transactions": txs })
+            }
+            NwcResult::GetBalance(balance) => serde_json::json!({ "balance": balance }),
+            NwcResult::GetInfo(info) => serde_json::to_value(info)?,
+        };
+        Ok(NwcResponse {
+            result_type: result_type.to_owned(),
+            error: None,
+            result: Some(value),
+        })
+    }
+
+    /// Create an error response
+    pub fn from_error(error: NwcError, result_type: &str) -> NwcResponse {
+        NwcResponse {
+            result_type: result_type.to_owned(),
+            error: Some(error),
+            result: None,
+        }
+    }
+
+    /// Interpret the result according to `result_type`.
+    ///
+    /// Returns Ok(None) if the response carries no result (e.g. it is an error).
+    pub fn typed_result(&self) -> Result<Option<NwcResult>, Error> {
+        let value = match &self.result {
+            Some(v) if !v.is_null() => v.clone(),
+            _ => return Ok(None),
+        };
+
+        #[derive(Deserialize)]
+        struct Payment {
+            preimage: String,
+            #[serde(default)]
+            fees_paid: Option<u64>,
+        }
+
+        #[derive(Deserialize)]
+        struct Transactions {
+            transactions: Vec<NwcTransaction>,
+        }
+
+        #[derive(Deserialize)]
+        struct Balance {
+            balance: u64,
+        }
+
+        let result = match &*self.result_type {
+            "pay_invoice" | "multi_pay_invoice" => {
+                let p: Payment = serde_json::from_value(value)?;
+                NwcResult::Payment {
+                    preimage: p.preimage,
+                    fees_paid: p.fees_paid,
+                }
+            }
+            "make_invoice" => NwcResult::MakeInvoice(serde_json::from_value(value)?),
+            "lookup_invoice" => NwcResult::LookupInvoice(serde_json::from_value(value)?),
+            "list_transactions" => {
+                let t: Transactions = serde_json::from_value(value)?;
+                NwcResult::ListTransactions(t.transactions)
+            }
+            "get_balance" => {
+                let b: Balance = serde_json::from_value(value)?;
+                NwcResult::GetBalance(b.balance)
+            }
+            "get_info" => NwcResult::GetInfo(serde_json::from_value(value)?),
+            other => {
+                return Err(Error::WalletConnect(format!(
+                    "Unknown result type: {other}"
+                )))
+            }
+        };
+
+        Ok(Some(result))
+    }
+
+    /// Create a response event (kind 23195) as a wallet service, answering `request`
+    /// with the same encryption the request used.
+    ///
+    /// `d_tag` should be set for responses to `multi_pay_invoice`.
+    pub fn to_event<S>(
+        &self,
+        signer: &S,
+        request: &Event,
+        d_tag: Option<String>,
+    ) -> Result<Event, Error>
+    where
+        S: Signer,
+    {
+        let encryption = request_encryption(request);
+        let json = serde_json::to_string(self)?;
+        let content = signer.encrypt(&request.pubkey, &json, encryption.algorithm())?;
+
+        let mut tags = vec![
+            Tag::new(&["p", &request.pubkey.as_hex_string()]),
+            Tag::new(&["e", &request.id.as_hex_string()]),
+        ];
+        if let Some(d) = d_tag {
+            tags.push(Tag::new(&["d", &d]));
+        }
+        if encryption == NwcEncryption::Nip44v2 {
+            tags.push(Tag::new(&["encryption", encryption.as_str()]));
+        }
+
+        signer.sign_event(PreEvent {
+            pubkey: signer.public_key(),
+            created_at: Unixtime::now(),
+            kind: EventKind::WalletResponse,
+            tags,
+            content,
+        })
+    }
+}
+
+/// A notification from a wallet service (NIP-47)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "notification_type",
+    content = "notification",
+    rename_all = "snake_case"
+)]
+pub enum NwcNotification {
+    /// A payment was received
+    PaymentReceived(NwcTransaction),
+
+    /// A payment was sent
+    PaymentSent(NwcTransaction),
+}
+
+// The encryption a request event says it uses, NIP-04 if it does not say
+fn request_encryption(event: &Event) -> NwcEncryption {
+    event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "encryption")
+        .and_then(|t| NwcEncryption::from_str_opt(t.value()))
+        .unwrap_or(NwcEncryption::Nip04)
+}
+
+/// A Nostr Wallet Connect client (NIP-47)
+///
+/// This creates encrypted request events for the wallet service and decrypts the
+/// response events, matching each to the request it answers. It does no networking.
+#[derive(Debug)]
+pub struct NwcClient {
+    uri: NwcUri,
+    encryption: NwcEncryption,
+    pending: HashMap<Id, String>,
+}
+
+impl NwcClient {
+    /// Create a new client. NIP-04 is used until `set_info()` negotiates otherwise.
+    pub fn new(uri: NwcUri) -> NwcClient {
+        NwcClient {
+            uri,
+            encryption: NwcEncryption::Nip04,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// The connection URI
+    pub fn uri(&self) -> &NwcUri {
+        &self.uri
+    }
+
+    /// The encryption in use
+    pub fn encryption(&self) -> NwcEncryption {
+        self.encryption
+    }
+
+    /// Negotiate encryption from the wallet service's info event
+    pub fn set_info(&mut self, info: &NwcInfo) {
+        self.encryption = info.negotiate_encryption();
+    }
+
+    /// A filter for the events that answer this client: responses and notifications
+    /// from the wallet service tagged to our key
+    pub fn filter(&self) -> Filter {
+        let mut filter = Filter::new();
+        filter.add_author(self.uri.wallet_pubkey);
+        filter.add_event_kind(EventKind::WalletResponse);
+        filter.add_event_kind(self.encryption.notification_kind());
+        filter.add_tag_value('p', self.uri.secret.public_key().as_hex_string());
+        filter
+    }
+
+    /// Create an encrypted request event (kind 23194) and remember it so the
+    /// response can be matched to it
+    pub fn create_request_event(
+        &mut self,
+        request: &NwcRequest,
+        expiration: Option<Unixtime>,
+    ) -> Result<Event, Error> {
+        let json = serde_json::to_string(request)?;
+        let content =
+            self.uri
+                .secret
+                .encrypt(&self.uri.wallet_pubkey, &json, self.encryption.algorithm())?;
+
+        let mut tags = vec![Tag::new(&["p", &self.uri.wallet_pubkey.as_hex_string()])];
+        if self.encryption == NwcEncryption::Nip44v2 {
+            tags.push(Tag::new(&["encryption", self.encryption.as_str()]));
+        }
+        if let Some(exp) = expiration {
+            tags.push(ParsedTag::Expiration(exp).into_tag());
+        }
+
+        let event = self.uri.secret.sign_event(PreEvent {
+            pubkey: self.uri.secret.public_key(),
+            created_at: Unixtime::now(),
+            kind: EventKind::WalletRequest,
+            tags,
+            content,
+        })?;
+
+        let _ = self.pending.insert(event.id, request.method().to_owned());
+        Ok(event)
+    }
+
+    /// Decrypt a response event and match it to the request it answers.
+    ///
+    /// Returns the request event id, the 'd' tag (set for `multi_pay_invoice`), and
+    /// the response. The request stops being pending once answered, except for
+    /// `multi_pay_invoice` which gets several responses; use `forget_request()` for those.
+    pub fn handle_response_event(
+        &mut self,
+        event: &Event,
+    ) -> Result<(Id, Option<String>, NwcResponse), Error> {
+        if event.kind != EventKind::WalletResponse {
+            return Err(Error::WrongEventKind);
+        }
+        if event.pubkey != self.uri.wallet_pubkey {
+            return Err(Error::WalletConnect(
+                "Response is not from the wallet service".to_owned(),
+            ));
+        }
+        if !event.is_tagged(&self.uri.secret.public_key()) {
+            return Err(Error::InvalidRecipient);
+        }
+
+        let mut request_id: Option<Id> = None;
+        let mut d_tag: Option<String> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "e" if request_id.is_none() => {
+                    request_id = Some(Id::try_from_hex_string(tag.value())?);
+                }
+                "d" if d_tag.is_none() => d_tag = Some(tag.value().to_owned()),
+                _ => {}
+            }
+        }
+        let request_id = match request_id {
+            Some(id) => id,
+            None => {
+                return Err(Error::WalletConnect(
+                    "Response does not reference a request".to_owned(),
+                ))
+            }
+        };
+
+        let method = match self.pending.get(&request_id) {
+            Some(m) => m.clone(),
+            None => {
+                return Err(Error::WalletConnect(
+                    "Response does not match a pending request".to_owned(),
+                ))
+            }
+        };
+
+        let json = self.uri.secret.decrypt(&event.pubkey, &event.content)?;
+        let response: NwcResponse = serde_json::from_str(&json)?;
+
+        if method != "multi_pay_invoice" {
+            let _ = self.pending.remove(&request_id);
+        }
+
+        Ok((request_id, d_tag, response))
+    }
+
+    /// Stop waiting for responses to a request
+    pub fn forget_request(&mut self, id: Id) {
+        let _ = self.pending.remove(&id);
+    }
+
+    /// The ids of requests that have not been answered yet
+    pub fn pending_requests(&self) -> Vec<Id> {
+        self.pending.keys().copied().collect()
+    }
+
+    /// Decrypt a notification event (kind 23196 or 23197)
+    pub fn handle_notification_event(&self, event: &Event) -> Result<NwcNotification, Error> {
+        if event.kind != EventKind::WalletNotification
+            && event.kind != EventKind::WalletNotificationNip04
+        {
+            return Err(Error::WrongEventKind);
+        }
+        if event.pubkey != self.uri.wallet_pubkey {
+            return Err(Error::WalletConnect(
+                "Notification is not from the wallet service".to_owned(),
+            ));
+        }
+        let json = self.uri.secret.decrypt(&event.pubkey, &event.content)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nwc_uri() {
+        let wallet = PrivateKey::generate();
+        let mut secret = PrivateKey::generate();
+        let s = format!(
+            "nostr+walletconnect://{}?relay=wss%3A%2F%2Frelay.damus.io&secret={}&lud16=alice%40example.com",
+            wallet.public_key().as_hex_string(),
+            secret.as_hex_string()
+        );
+
+        let mut uri = NwcUri::try_from_str(&s).unwrap();
+        assert_eq!(uri.wallet_pubkey, wallet.public_key());
+        assert_eq!(uri.relays[0].as_str(), "wss://relay.damus.io/");
+        assert_eq!(uri.secret.public_key(), secret.public_key());
+        assert_eq!(uri.lud16.as_deref(), Some("alice@example.com"));
+
+        let uri2 = NwcUri::try_from_str(&uri.as_uri_string()).unwrap();
+        assert_eq!(uri2.wallet_pubkey, uri.wallet_pubkey);
+        assert_eq!(uri2.relays, uri.relays);
+
+        assert!(NwcUri::try_from_str("https://example.com").is_err());
+    }
+
+    #[test]
+    fn test_nwc_request_serde() {
+        let request = NwcRequest::PayInvoice {
+            invoice: "lnbc50n1...".to_owned(),
+            amount: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"method":"pay_invoice","params":{"invoice":"lnbc50n1..."}}"#
+        );
+
+        let request: NwcRequest =
+            serde_json::from_str(r#"{"method":"get_balance","params":{}}"#).unwrap();
+        assert_eq!(request, NwcRequest::GetBalance {});
+
+        let response: NwcResponse = serde_json::from_str(
+            r#"{"result_type":"pay_invoice","error":{"code":"SOMETHING_NEW","message":"no"},"result":null}"#,
+        )
+        .unwrap();
+        assert_eq!(response.error.unwrap().code, NwcErrorCode::Other);
+    }
+
+    #[test]
+    fn test_nwc_client_round_trip() {
+        let wallet = PrivateKey::generate();
+        let mut secret = PrivateKey::generate();
+        let s = format!(
+            "nostr+walletconnect://{}?relay=wss%3A%2F%2Frelay.example.com&secret={}",
+            wallet.public_key().as_hex_string(),
+            secret.as_hex_string()
+        );
+        let mut client = NwcClient::new(NwcUri::try_from_str(&s).unwrap());
+
+        let info_event = wallet
+            .sign_event(PreEvent {
+                pubkey: wallet.public_key(),
+                created_at: Unixtime::now(),
+                kind: EventKind::WalletInfo,
+                tags: vec![Tag::new(&["encryption", "nip44_v2 nip04"])],
+                content: "pay_invoice get_balance".to_owned(),
+            })
+            .unwrap();
+        let info = NwcInfo::from_event(&info_event).unwrap();
+        assert!(info.supports("get_balance"));
+        client.set_info(&info);
+        assert_eq!(client.encryption(), NwcEncryption::Nip44v2);
+
+        let request_event = client
+            .create_request_event(&NwcRequest::GetBalance {}, None)
+            .unwrap();
+
+        // The wallet service side
+        let json = wallet
+            .decrypt(&request_event.pubkey, &request_event.content)
+            .unwrap();
+        let request: NwcRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(request, NwcRequest::GetBalance {});
+        let response =
+            NwcResponse::from_result(&NwcResult::GetBalance(10_000), request.method()).unwrap();
+        let response_event = response.to_event(&wallet, &request_event, None).unwrap();
+
+        let (id, d, response) = client.handle_response_event(&response_event).unwrap();
+        assert_eq!(id, request_event.id);
+        assert_eq!(d, None);
+        assert_eq!(
+            response.typed_result().unwrap(),
+            Some(NwcResult::GetBalance(10_000))
+        );
+        assert!(client.pending_requests().is_empty());
+
+        // A second copy no longer matches a pending request
+        assert!(client.handle_response_event(&response_event).is_err());
+    }
+}
@@ -0,0 +1,332 @@
+use crate::types::{
+    Event, EventKind, Id, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+use std::collections::HashMap;
+
+/// Whether a poll allows one choice or several
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PollType {
+    /// Only one option may be chosen
+    #[default]
+    SingleChoice,
+
+    /// Any number of options may be chosen
+    MultipleChoice,
+}
+
+impl PollType {
+    /// As a str, as used in the 'polltype' tag
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PollType::SingleChoice => "singlechoice",
+            PollType::MultipleChoice => "multiplechoice",
+        }
+    }
+
+    /// From a str, as used in the 'polltype' tag
+    pub fn from_str_opt(s: &str) -> Option<PollType> {
+        match s {
+            "singlechoice" => Some(PollType::SingleChoice),
+            "multiplechoice" => Some(PollType::MultipleChoice),
+            _ => None,
+        }
+    }
+}
+
+/// An option in a poll
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PollOption {
+    /// The option id, which responses refer to
+    pub id: String,
+
+    /// The option text
+    pub label: String,
+}
+
+/// A NIP-88 poll (kind 1068)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Poll {
+    /// The question
+    pub question: String,
+
+    /// The options
+    pub options: Vec<PollOption>,
+
+    /// Whether one or several options may be chosen
+    pub poll_type: PollType,
+
+    /// When the poll closes. Responses after this are not counted.
+    pub ends_at: Option<Unixtime>,
+
+    /// Relays where responses should be sent, and collected from
+    pub relays: Vec<UncheckedUrl>,
+}
+
+impl Poll {
+    /// Create a new poll
+    pub fn new(question: String, options: Vec<PollOption>, poll_type: PollType) -> Poll {
+        Poll {
+            question,
+            options,
+            poll_type,
+            ..Default::default()
+        }
+    }
+
+    /// Parse a kind-1068 Poll event
+    pub fn from_event(event: &Event) -> Result<Poll, Error> {
+        if event.kind != EventKind::Poll {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut poll = Poll {
+            question: event.content.clone(),
+            ..Default::default()
+        };
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "option" => poll.options.push(PollOption {
+                    id: tag.value().to_owned(),
+                    label: tag.get_index(2).to_owned(),
+                }),
+                "polltype" => {
+                    poll.poll_type = PollType::from_str_opt(tag.value()).unwrap_or_default()
+                }
+                "endsAt" => poll.ends_at = Some(Unixtime(tag.value().parse()?)),
+                "relay" => poll.relays.push(UncheckedUrl(tag.value().to_owned())),
+                _ => {}
+            }
+        }
+        Ok(poll)
+    }
+
+    /// Create the kind-1068 Poll PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags: Vec<Tag> = Vec::new();
+        for option in self.options.iter() {
+            tags.push(Tag::new(&["option", &option.id, &option.label]));
+        }
+        for relay in self.relays.iter() {
+            tags.push(Tag::new(&["relay", &relay.0]));
+        }
+        tags.push(Tag::new(&["polltype", self.poll_type.as_str()]));
+        if let Some(ends_at) = self.ends_at {
+            tags.push(Tag::new(&["endsAt", &ends_at.0.to_string()]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::Poll,
+            tags,
+            content: self.question.clone(),
+        }
+    }
+
+    /// Count the responses to the poll `poll_id`, returning each option id with
+    /// its count, in option order.
+    ///
+    /// Only each pubkey's latest response made before the poll closes is counted.
+    /// A single-choice response counts only its first response, if that is a known
+    /// option. Responses with less than `min_pow` bits of proof-of-work, or (if
+    /// `follows` is given) from pubkeys not in `follows`, are ignored.
+    ///
+    /// Signatures are not checked here.
+    pub fn tally(
+        &self,
+        poll_id: Id,
+        responses: &[Event],
+        min_pow: u8,
+        follows: Option<&[PublicKey]>,
+    ) -> Vec<(String, usize)> {
+        let mut latest: HashMap<PublicKey, (&Event, PollResponse)> = HashMap::new();
+        for event in responses.iter() {
+            if self.ends_at.is_some_and(|end| event.created_at > end)
+                || follows.is_some_and(|f| !f.contains(&event.pubkey))
+                || (min_pow > 0 && event.pow() < min_pow)
+            {
+                continue;
+            }
+            let response = match PollResponse::from_event(event) {
+                Ok(response) if response.poll == poll_id => response,
+                _ => continue,
+            };
+            let newer = match latest.get(&event.pubkey) {
+                Some((current, _)) => {
+                    (event.created_at, std::cmp::Reverse(event.id))
+                        > (current.created_at, std::cmp::Reverse(current.id))
+                }
+                None => true,
+            };
+            if newer {
+                let _ = latest.insert(event.pubkey, (event, response));
+            }
+        }
+
+        let mut counts: Vec<(String, usize)> =
+            self.options.iter().map(|o| (o.id.clone(), 0)).collect();
+        for (_, response) in latest.values() {
+            let limit = match self.poll_type {
+                PollType::SingleChoice => 1,
+                PollType::MultipleChoice => usize::MAX,
+            };
+            let mut chosen: Vec<&String> = Vec::new();
+            for id in response.responses.iter().take(limit) {
+                if self.options.iter().any(|o| &o.id == id) && !chosen.contains(&id) {
+                    chosen.push(id);
+                }
+            }
+            for (id, count) in counts.iter_mut() {
+                if chosen.contains(&&*id) {
+                    *count += 1;
+                }
+            }
+        }
+        counts
+    }
+}
+
+/// A NIP-88 response to a poll (kind 1018)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PollResponse {
+    /// The poll
+    pub poll: Id,
+
+    /// The ids of the chosen options
+    pub responses: Vec<String>,
+}
+
+impl PollResponse {
+    /// Create a new response to a poll
+    pub fn new(poll: Id, responses: Vec<String>) -> PollResponse {
+        PollResponse { poll, responses }
+    }
+
+    /// Parse a kind-1018 PollResponse event
+    pub fn from_event(event: &Event) -> Result<PollResponse, Error> {
+        if event.kind != EventKind::PollResponse {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut poll: Option<Id> = None;
+        let mut responses: Vec<String> = Vec::new();
+        for tag in event.tags.iter() {
+            if tag.tagname() == "response" {
+                responses.push(tag.value().to_owned());
+            } else if let Ok(ParsedTag::Event { id, .. }) = tag.parse() {
+                poll = poll.or(Some(id));
+            }
+        }
+
+        Ok(PollResponse {
+            poll: poll.ok_or(Error::TagMismatch)?,
+            responses,
+        })
+    }
+
+    /// Create the kind-1018 PollResponse PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["e", &self.poll.as_hex_string()])];
+        for response in self.responses.iter() {
+            tags.push(Tag::new(&["response", response]));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::PollResponse,
+            tags,
+            content: "".to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_poll_round_trip() {
+        let private_key = PrivateKey::generate();
+        let mut poll = Poll::new(
+            "Best pizza topping?".to_owned(),
+            vec![
+                PollOption {
+                    id: "a".to_owned(),
+                    label: "Pineapple".to_owned(),
+                },
+                PollOption {
+                    id: "b".to_owned(),
+                    label: "Mushroom".to_owned(),
+                },
+            ],
+            PollType::MultipleChoice,
+        );
+        poll.ends_at = Some(Unixtime(1_800_000_000));
+        poll.relays
+            .push(UncheckedUrl("wss://relay.example.com".to_owned()));
+        let event = private_key
+            .sign_event(poll.to_pre_event(private_key.public_key()))
+            .unwrap();
+        assert_eq!(Poll::from_event(&event).unwrap(), poll);
+    }
+
+    #[test]
+    fn test_poll_tally() {
+        let poll_id = Id::mock();
+        let mut poll = Poll::new(
+            "Tabs or spaces?".to_owned(),
+            vec![
+                PollOption {
+                    id: "tabs".to_owned(),
+                    label: "Tabs".to_owned(),
+                },
+                PollOption {
+                    id: "spaces".to_owned(),
+                    label: "Spaces".to_owned(),
+                },
+            ],
+            PollType::SingleChoice,
+        );
+        poll.ends_at = Some(Unixtime(2000));
+
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+        let carol = PrivateKey::generate();
+        let dave = PrivateKey::generate();
+        let respond = |key: &PrivateKey, choices: &[&str], at: i64| {
+            let response =
+                PollResponse::new(poll_id, choices.iter().map(|c| (*c).to_owned()).collect());
+            let mut pre_event = response.to_pre_event(key.public_key());
+            pre_event.created_at = Unixtime(at);
+            key.sign_event(pre_event).unwrap()
+        };
+
+        let responses = vec![
+            // Alice changes her mind, but only her latest response counts
+            respond(&alice, &["tabs"], 1000),
+            respond(&alice, &["spaces"], 1500),
+            // Bob's change of mind is too late
+            respond(&bob, &["tabs", "spaces"], 1000),
+            respond(&bob, &["spaces"], 3000),
+            respond(&carol, &["spaces"], 1200),
+            // Only the first response counts, and it is not an option
+            respond(&dave, &["bogus", "tabs"], 1300),
+        ];
+
+        assert_eq!(
+            poll.tally(poll_id, &responses, 0, None),
+            vec![("tabs".to_owned(), 1), ("spaces".to_owned(), 2)]
+        );
+        assert_eq!(
+            poll.tally(poll_id, &responses, 0, Some(&[bob.public_key()])),
+            vec![("tabs".to_owned(), 1), ("spaces".to_owned(), 0)]
+        );
+        assert!(poll
+            .tally(poll_id, &responses, 255, None)
+            .iter()
+            .all(|(_, count)| *count == 0));
+    }
+}
@@ -0,0 +1,291 @@
+use crate::types::{
+    Event, Filter, NEvent, Profile, PublicKey, RelayList, RelayListUsage, RelayUrl,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// The relays to read from and the filter to send each one
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RelayReadPlan {
+    /// The filter to send to each relay
+    pub filters: BTreeMap<RelayUrl, Filter>,
+
+    /// Authors who could not be covered by as many relays as were requested,
+    /// with the number of relays that do cover them
+    pub uncovered: Vec<(PublicKey, usize)>,
+}
+
+/// Plans which relays to read from and write to, following the outbox model
+///
+/// Authors are read from the relays they write to (their outboxes, from their
+/// kind-10002 RelayList). Events are written to the author's outboxes and to the
+/// inboxes of the people they tag. Relay hints (from `nprofile`s, `nevent`s and
+/// the like) are used for authors without a RelayList.
+#[derive(Clone, Debug)]
+pub struct RelayPlanner {
+    /// How many relays to read each author from
+    pub relays_per_author: usize,
+
+    /// The most relays a read plan may use
+    pub max_relays: usize,
+
+    /// Relays never to use
+    pub blocked: HashSet<RelayUrl>,
+
+    relay_lists: HashMap<PublicKey, RelayList>,
+    hints: HashMap<PublicKey, Vec<RelayUrl>>,
+}
+
+impl Default for RelayPlanner {
+    fn default() -> RelayPlanner {
+        RelayPlanner::new(2, 50)
+    }
+}
+
+impl RelayPlanner {
+    /// Create a new RelayPlanner
+    pub fn new(relays_per_author: usize, max_relays: usize) -> RelayPlanner {
+        RelayPlanner {
+            relays_per_author,
+            max_relays,
+            blocked: HashSet::new(),
+            relay_lists: HashMap::new(),
+            hints: HashMap::new(),
+        }
+    }
+
+    /// Never use this relay
+    pub fn block(&mut self, relay: RelayUrl) {
+        let _ = self.blocked.insert(relay);
+    }
+
+    /// Set the RelayList of a person
+    pub fn set_relay_list(&mut self, pubkey: PublicKey, relay_list: RelayList) {
+        let _ = self.relay_lists.insert(pubkey, relay_list);
+    }
+
+    /// Add a hint that a person writes to a relay
+    pub fn add_hint(&mut self, pubkey: PublicKey, relay: RelayUrl) {
+        let hints = self.hints.entry(pubkey).or_default();
+        if !hints.contains(&relay) {
+            hints.push(relay);
+        }
+    }
+
+    /// Add the relay hints in a Profile
+    pub fn add_profile_hints(&mut self, profile: &Profile) {
+        for url in profile.relays.iter() {
+            if let Ok(relay) = RelayUrl::try_from_unchecked_url(url) {
+                self.add_hint(profile.pubkey, relay);
+            }
+        }
+    }
+
+    /// Add the relay hints in an NEvent, if it names its author
+    pub fn add_nevent_hints(&mut self, nevent: &NEvent) {
+        if let Some(author) = nevent.author {
+            for url in nevent.relays.iter() {
+                if let Ok(relay) = RelayUrl::try_from_unchecked_url(url) {
+                    self.add_hint(author, relay);
+                }
+            }
+        }
+    }
+
+    // The unblocked relays in a RelayList with the given usage, sorted
+    fn relays_for(&self, pubkey: &PublicKey, usage: RelayListUsage) -> Vec<RelayUrl> {
+        let mut relays: Vec<RelayUrl> = match self.relay_lists.get(pubkey) {
+            Some(list) => list
+                .0
+                .iter()
+                .filter(|(_, u)| **u == usage || **u == RelayListUsage::Both)
+                .map(|(r, _)| r.clone())
+                .filter(|r| !self.blocked.contains(r))
+                .collect(),
+            None => vec![],
+        };
+        relays.sort();
+        relays
+    }
+
+    /// The relays a person writes to. Hints are used if they have no RelayList
+    /// outboxes.
+    pub fn outboxes(&self, pubkey: &PublicKey) -> Vec<RelayUrl> {
+        let relays = self.relays_for(pubkey, RelayListUsage::Outbox);
+        if !relays.is_empty() {
+            return relays;
+        }
+        match self.hints.get(pubkey) {
+            Some(hints) => hints
+                .iter()
+                .filter(|r| !self.blocked.contains(*r))
+                .cloned()
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// The relays a person reads from
+    pub fn inboxes(&self, pubkey: &PublicKey) -> Vec<RelayUrl> {
+        self.relays_for(pubkey, RelayListUsage::Inbox)
+    }
+
+    /// Plan where to read the events of `authors`.
+    ///
+    /// Relays are picked greedily, each time taking the relay that covers the most
+    /// authors still needing relays, until every author is covered by
+    /// `relays_per_author` relays or `max_relays` is reached. Each relay's filter is
+    /// `template` with the authors it covers.
+    pub fn read_plan(&self, authors: &[PublicKey], template: &Filter) -> RelayReadPlan {
+        let candidates: Vec<(PublicKey, Vec<RelayUrl>)> =
+            authors.iter().map(|pk| (*pk, self.outboxes(pk))).collect();
+
+        let mut assigned: Vec<Vec<RelayUrl>> = vec![vec![]; candidates.len()];
+        let mut filters: BTreeMap<RelayUrl, Filter> = BTreeMap::new();
+
+        while filters.len() < self.max_relays {
+            // Count how many needy authors each unused relay would cover
+            let mut counts: BTreeMap<&RelayUrl, usize> = BTreeMap::new();
+            for (i, (_, relays)) in candidates.iter().enumerate() {
+                if assigned[i].len() >= self.relays_per_author {
+                    continue;
+                }
+                for relay in relays.iter() {
+                    if !filters.contains_key(relay) {
+                        *counts.entry(relay).or_default() += 1;
+                    }
+                }
+            }
+
+            // Most coverage wins, ties go to the lowest url so plans are stable
+            let mut best: Option<(&RelayUrl, usize)> = None;
+            for (relay, count) in counts.iter() {
+                match best {
+                    Some((_, c)) if c >= *count => {}
+                    _ => best = Some((relay, *count)),
+                }
+            }
+            let relay = match best {
+                Some((r, _)) => r.clone(),
+                None => break,
+            };
+
+            let mut filter = template.clone();
+            filter.authors.clear();
+            for (i, (pk, relays)) in candidates.iter().enumerate() {
+                if assigned[i].len() < self.relays_per_author && relays.contains(&relay) {
+                    assigned[i].push(relay.clone());
+                    filter.add_author(*pk);
+                }
+            }
+            let _ = filters.insert(relay, filter);
+        }
+
+        let uncovered = candidates
+            .iter()
+            .zip(assigned.iter())
+            .filter(|(_, a)| a.len() < self.relays_per_author)
+            .map(|((pk, _), a)| (*pk, a.len()))
+            .collect();
+
+        RelayReadPlan { filters, uncovered }
+    }
+
+    /// Plan where to publish an event: the author's outboxes, and up to
+    /// `relays_per_author` inboxes of each person tagged.
+    ///
+    /// `max_relays` does not apply, so that no tagged person is missed.
+    pub fn write_plan(&self, event: &Event) -> Vec<RelayUrl> {
+        let mut output: Vec<RelayUrl> = self.outboxes(&event.pubkey);
+
+        for (pubkey, _, _) in event.people() {
+            if pubkey == event.pubkey {
+                continue;
+            }
+            for relay in self
+                .inboxes(&pubkey)
+                .into_iter()
+                .take(self.relays_per_author)
+            {
+                if !output.contains(&relay) {
+                    output.push(relay);
+                }
+            }
+        }
+
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EventKind, PreEvent, PrivateKey, Signer, Tag, Unixtime};
+
+    fn relay(n: usize) -> RelayUrl {
+        RelayUrl::try_from_str(&format!("wss://relay{n}.example.com")).unwrap()
+    }
+
+    fn relay_list(entries: &[(usize, RelayListUsage)]) -> RelayList {
+        RelayList(entries.iter().map(|(n, u)| (relay(*n), *u)).collect())
+    }
+
+    #[test]
+    fn test_read_plan() {
+        let a = PublicKey::mock();
+        let b = PublicKey::mock();
+        let c = PublicKey::mock();
+        let d = PublicKey::mock();
+
+        let mut planner = RelayPlanner::new(2, 10);
+        use RelayListUsage::*;
+        planner.set_relay_list(a, relay_list(&[(1, Both), (2, Outbox), (9, Inbox)]));
+        planner.set_relay_list(b, relay_list(&[(1, Outbox), (3, Both)]));
+        planner.set_relay_list(c, relay_list(&[(1, Outbox), (2, Outbox), (4, Outbox)]));
+        planner.add_hint(d, relay(5));
+        planner.block(relay(4));
+
+        let mut template = Filter::new();
+        template.add_event_kind(EventKind::TextNote);
+        let plan = planner.read_plan(&[a, b, c, d], &template);
+
+        // relay1 covers a, b and c; relay2 covers a and c; b also needs relay3
+        assert_eq!(plan.filters.len(), 4);
+        assert_eq!(plan.filters[&relay(1)].authors, vec![a, b, c]);
+        assert_eq!(plan.filters[&relay(2)].authors, vec![a, c]);
+        assert_eq!(plan.filters[&relay(3)].authors, vec![b]);
+        assert_eq!(plan.filters[&relay(5)].authors, vec![d]);
+        assert_eq!(plan.filters[&relay(5)].kinds, vec![EventKind::TextNote]);
+        assert!(!plan.filters.contains_key(&relay(9)));
+        assert_eq!(plan.uncovered, vec![(d, 1)]);
+
+        let mut planner = planner.clone();
+        planner.max_relays = 1;
+        let plan = planner.read_plan(&[a, b, c, d], &template);
+        assert_eq!(plan.filters.len(), 1);
+        assert_eq!(plan.uncovered.len(), 4);
+    }
+
+    #[test]
+    fn test_write_plan() {
+        let private_key = PrivateKey::generate();
+        let author = private_key.public_key();
+        let tagged = PublicKey::mock();
+
+        let mut planner = RelayPlanner::new(1, 10);
+        use RelayListUsage::*;
+        planner.set_relay_list(author, relay_list(&[(1, Outbox), (2, Inbox)]));
+        planner.set_relay_list(tagged, relay_list(&[(3, Inbox), (4, Inbox), (5, Outbox)]));
+
+        let event = private_key
+            .sign_event(PreEvent {
+                pubkey: author,
+                created_at: Unixtime::mock(),
+                kind: EventKind::TextNote,
+                tags: vec![Tag::new(&["p", &tagged.as_hex_string()])],
+                content: "hello".to_owned(),
+            })
+            .unwrap();
+
+        assert_eq!(planner.write_plan(&event), vec![relay(1), relay(3)]);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::versioned::tag3::TagV3;
 use crate::{
-    DelegationConditions, Error, EventKind, EventReference, Id, IntoVec, NAddr, PublicKey,
-    RelayUrl, Signature, UncheckedUrl,
+    DelegationConditions, Error, EventKind, EventReference, Id, IdentityPlatform, IntoVec, NAddr,
+    PublicKey, RelayUrl, Signature, UncheckedUrl, Unixtime,
 };
 
 /// A tag on an Event
@@ -30,6 +30,13 @@ pub enum ParsedTag {
         marker: Option<String>,
         author_pubkey: Option<PublicKey>,
     },
+    Expiration(Unixtime),
+    ExternalIdentity {
+        platform: IdentityPlatform,
+        identity: String,
+        proof: String,
+    },
+    Group(String),
     Hashtag(String),
     Identifier(String),
     Kind(EventKind),
@@ -37,6 +44,7 @@ pub enum ParsedTag {
         nonce: u32,
         target: Option<u32>,
     },
+    Previous(Vec<String>),
     Proxy {
         id: String,
         protocol: String,
@@ -74,6 +82,11 @@ pub enum ParsedTag {
     Subject(String),
     Summary(String),
     Title(String),
+    Zap {
+        pubkey: PublicKey,
+        recommended_relay_url: Option<UncheckedUrl>,
+        weight: Option<u64>,
+    },
     Unmatched(Vec<String>),
 }
 
@@ -162,12 +175,35 @@ impl ParsedTag {
                     author_pubkey,
                 })
             }
+            "expiration" => Ok(ParsedTag::Expiration(Unixtime(
+                tag.get_opt_index(1)
+                    .ok_or(Error::TagMismatch)?
+                    .parse::<i64>()?,
+            ))),
             "t" => Ok(ParsedTag::Hashtag(
                 tag.get_opt_index(1).ok_or(Error::TagMismatch)?.to_string(),
             )),
             "d" => Ok(ParsedTag::Identifier(
                 tag.get_opt_index(1).ok_or(Error::TagMismatch)?.to_string(),
             )),
+            "h" => Ok(ParsedTag::Group(
+                tag.get_opt_index(1).ok_or(Error::TagMismatch)?.to_string(),
+            )),
+            "i" => {
+                // 'i' tags are also used outside of NIP-39, leave those unmatched
+                let claim = tag.get_opt_index(1).ok_or(Error::TagMismatch)?;
+                let platform = claim
+                    .split_once(':')
+                    .and_then(|(p, identity)| Some((IdentityPlatform::from_str_opt(p)?, identity)));
+                match (platform, tag.get_opt_index(2)) {
+                    (Some((platform, identity)), Some(proof)) => Ok(ParsedTag::ExternalIdentity {
+                        platform,
+                        identity: identity.to_string(),
+                        proof: proof.to_string(),
+                    }),
+                    _ => Ok(ParsedTag::Unmatched(tag.clone().into_inner())),
+                }
+            }
             "k" => Ok(ParsedTag::Kind(
                 tag.get_opt_index(1)
                     .ok_or(Error::TagMismatch)?
@@ -185,6 +221,13 @@ impl ParsedTag {
                 };
                 Ok(ParsedTag::Nonce { nonce, target })
             }
+            "previous" => {
+                let mut ids: Vec<String> = Vec::new();
+                for i in 1..tag.len() {
+                    ids.push(tag.get_index(i).to_string());
+                }
+                Ok(ParsedTag::Previous(ids))
+            }
             "proxy" => Ok(ParsedTag::Proxy {
                 id: tag.get_opt_index(1).ok_or(Error::TagMismatch)?.to_string(),
                 protocol: tag.get_opt_index(2).ok_or(Error::TagMismatch)?.to_string(),
@@ -307,6 +350,21 @@ impl ParsedTag {
             "title" => Ok(ParsedTag::Title(
                 tag.get_opt_index(1).ok_or(Error::TagMismatch)?.to_string(),
             )),
+            "zap" => {
+                let pubkey = PublicKey::try_from_hex_string(
+                    tag.get_opt_index(1).ok_or(Error::TagMismatch)?,
+                    true,
+                )?;
+                let recommended_relay_url =
+                    tag.get_opt_index(2).map(|s| UncheckedUrl(s.to_string()));
+                // An unparsable weight is treated the same as a missing one
+                let weight = tag.get_opt_index(3).and_then(|s| s.parse::<u64>().ok());
+                Ok(ParsedTag::Zap {
+                    pubkey,
+                    recommended_relay_url,
+                    weight,
+                })
+            }
             _ => Ok(ParsedTag::Unmatched(tag.clone().into_inner())),
         }
     }
@@ -369,6 +427,17 @@ impl ParsedTag {
                 }
                 tag
             }
+            Expiration(t) => Tag::from_strings(vec!["expiration".to_string(), t.0.to_string()]),
+            ExternalIdentity {
+                platform,
+                identity,
+                proof,
+            } => Tag::from_strings(vec![
+                "i".to_string(),
+                format!("{}:{}", platform.as_str(), identity),
+                proof,
+            ]),
+            Group(s) => Tag::from_strings(vec!["h".to_string(), s]),
             Hashtag(s) => Tag::from_strings(vec!["t".to_string(), s]),
             Identifier(s) => Tag::from_strings(vec!["d".to_string(), s]),
             Kind(k) => {
@@ -382,6 +451,11 @@ impl ParsedTag {
                 }
                 tag
             }
+            Previous(ids) => {
+                let mut tag = Tag::new(&["previous"]);
+                tag.push_values(ids);
+                tag
+            }
             Proxy { id, protocol } => Tag::from_strings(vec!["proxy".to_owned(), id, protocol]),
             Pubkey {
                 pubkey,
@@ -479,6 +553,21 @@ impl ParsedTag {
             Subject(s) => Tag::from_strings(vec!["subject".to_string(), s]),
             Summary(s) => Tag::from_strings(vec!["summary".to_string(), s]),
             Title(s) => Tag::from_strings(vec!["title".to_string(), s]),
+            Zap {
+                pubkey,
+                recommended_relay_url,
+                weight,
+            } => {
+                let mut tag = Tag::new(&["zap"]);
+                tag.set_index(1, pubkey.as_hex_string());
+                if let Some(u) = recommended_relay_url {
+                    tag.set_index(2, u.0);
+                }
+                if let Some(w) = weight {
+                    tag.set_index(3, format!("{}", w));
+                }
+                tag
+            }
             Unmatched(vec) => Tag::from_strings(vec),
         }
     }
@@ -546,10 +635,15 @@ mod test {
             vec!["delegation", "f7234bd4c1394dda46d09f35bd384dd30cc552ad5541990f98844fb06676e9ca", "kind=1&created_at<1675721813", "6f44d7fe4f1c09f3954640fb58bd12bae8bb8ff4120853c4693106c82e920e2b898f1f9ba9bd65449a987c39c0423426ab7b53910c0c6abfb41b30bc16e5f524"],
             vec!["e", "5c83da77af1dec6d7289834998ad7aafbd9e2191396d75ec3cc27f5a77226f36", "wss://nostr.example.com", "f7234bd4c1394dda46d09f35bd384dd30cc552ad5541990f98844fb06676e9ca"],
             vec!["e", "5c83da77af1dec6d7289834998ad7aafbd9e2191396d75ec3cc27f5a77226f36", "", "f7234bd4c1394dda46d09f35bd384dd30cc552ad5541990f98844fb06676e9ca"],
+            vec!["expiration", "1700000000"],
+            vec!["i", "github:semisol", "9721ce4ee4fceb91c9711ca2a6c9a5ab"],
+            vec!["i", "mastodon:bitcoinhackers.org/@semisol", "109775066355589974"],
             vec!["t", "bitcoin"],
+            vec!["h", "pizza-lovers"],
             vec!["d", "20241214-blog"],
             vec!["k", "1111"],
             vec!["nonce", "24234234", "24"],
+            vec!["previous", "a1b2c3d4", "e5f6a7b8"],
             vec!["proxy", "blah blah", "mastodon bridge"],
             vec!["p", "f7234bd4c1394dda46d09f35bd384dd30cc552ad5541990f98844fb06676e9ca"],
             vec!["q", "5c83da77af1dec6d7289834998ad7aafbd9e2191396d75ec3cc27f5a77226f36", "wss://nos.lol"],
@@ -562,6 +656,8 @@ mod test {
             vec!["subject", "crazy stuff"],
             vec!["summary", "dont bother"],
             vec!["title", "my blog"],
+            vec!["zap", "f7234bd4c1394dda46d09f35bd384dd30cc552ad5541990f98844fb06676e9ca", "wss://nostr.oxtr.dev", "1"],
+            vec!["zap", "f7234bd4c1394dda46d09f35bd384dd30cc552ad5541990f98844fb06676e9ca", "", "2"],
         ];
 
         for input in inputs {
@@ -579,5 +675,9 @@ mod test {
             // Be sure it converts back identically
             assert_eq!(tag, parsed_tag.into_tag());
         }
+
+        // An 'i' tag without a proof is not a NIP-39 claim
+        let tag = Tag::new(&["i", "github:semisol"]);
+        assert!(matches!(tag.parse().unwrap(), ParsedTag::Unmatched(_)));
     }
 }
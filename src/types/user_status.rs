@@ -0,0 +1,216 @@
+use crate::types::{
+    Event, EventKind, Id, ParsedTag, PreEvent, PublicKey, Tag, UncheckedUrl, Unixtime,
+};
+use crate::Error;
+use std::collections::HashMap;
+
+/// The type of a user status, from its 'd' tag
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum UserStatusType {
+    /// What the user is doing
+    General,
+
+    /// What the user is listening to
+    Music,
+
+    /// Some other type
+    Other(String),
+}
+
+impl UserStatusType {
+    /// The 'd' tag value
+    pub fn as_str(&self) -> &str {
+        match self {
+            UserStatusType::General => "general",
+            UserStatusType::Music => "music",
+            UserStatusType::Other(s) => s,
+        }
+    }
+
+    /// Parse a 'd' tag value
+    pub fn from_d_tag(s: &str) -> UserStatusType {
+        match s {
+            "general" => UserStatusType::General,
+            "music" => UserStatusType::Music,
+            other => UserStatusType::Other(other.to_owned()),
+        }
+    }
+}
+
+/// A NIP-38 user status (kind 30315)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserStatus {
+    /// Whose status this is
+    pub pubkey: PublicKey,
+
+    /// The type of status
+    pub status_type: UserStatusType,
+
+    /// The status text. Empty means the status is cleared.
+    pub content: String,
+
+    /// A link to something about the status (its 'r' tag)
+    pub link: Option<UncheckedUrl>,
+
+    /// When the status expires (NIP-40)
+    pub expiration: Option<Unixtime>,
+
+    /// When the status was set
+    pub created_at: Unixtime,
+}
+
+impl UserStatus {
+    /// Create a new status, set now
+    pub fn new(pubkey: PublicKey, status_type: UserStatusType, content: String) -> UserStatus {
+        UserStatus {
+            pubkey,
+            status_type,
+            content,
+            link: None,
+            expiration: None,
+            created_at: Unixtime::now(),
+        }
+    }
+
+    /// Parse a kind-30315 UserStatus event
+    pub fn from_event(event: &Event) -> Result<UserStatus, Error> {
+        if event.kind != EventKind::UserStatus {
+            return Err(Error::WrongEventKind);
+        }
+
+        let link = event
+            .tags
+            .iter()
+            .find(|tag| tag.tagname() == "r")
+            .map(|tag| UncheckedUrl(tag.value().to_owned()));
+
+        Ok(UserStatus {
+            pubkey: event.pubkey,
+            status_type: UserStatusType::from_d_tag(&event.parameter().unwrap_or_default()),
+            content: event.content.clone(),
+            link,
+            expiration: event.expiration(),
+            created_at: event.created_at,
+        })
+    }
+
+    /// Create the kind-30315 UserStatus PreEvent
+    pub fn to_pre_event(&self) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", self.status_type.as_str()])];
+        if let Some(link) = &self.link {
+            tags.push(Tag::new(&["r", &link.0]));
+        }
+        if let Some(expiration) = self.expiration {
+            tags.push(ParsedTag::Expiration(expiration).into_tag());
+        }
+
+        PreEvent {
+            pubkey: self.pubkey,
+            created_at: self.created_at,
+            kind: EventKind::UserStatus,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+
+    /// Whether the status has expired as of `now`
+    pub fn is_expired(&self, now: Unixtime) -> bool {
+        matches!(self.expiration, Some(e) if e <= now)
+    }
+
+    /// Whether the status was cleared (its content is empty)
+    pub fn is_cleared(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// The current statuses of `pubkey` as of `now`, one per type, from a set of
+    /// events.
+    ///
+    /// For each type the latest event replaces the earlier ones (ties go to the
+    /// lowest id, as relays do). If that latest status has expired or was cleared,
+    /// the user has no status of that type; an earlier status does not come back.
+    pub fn current_all(events: &[Event], pubkey: PublicKey, now: Unixtime) -> Vec<UserStatus> {
+        let mut latest: HashMap<UserStatusType, (Id, UserStatus)> = HashMap::new();
+        for event in events.iter() {
+            if event.pubkey != pubkey {
+                continue;
+            }
+            let status = match UserStatus::from_event(event) {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            let replace = match latest.get(&status.status_type) {
+                Some((id, s)) => {
+                    status.created_at > s.created_at
+                        || (status.created_at == s.created_at && event.id < *id)
+                }
+                None => true,
+            };
+            if replace {
+                let _ = latest.insert(status.status_type.clone(), (event.id, status));
+            }
+        }
+
+        let mut output: Vec<UserStatus> = latest
+            .into_values()
+            .map(|(_, s)| s)
+            .filter(|s| !s.is_expired(now) && !s.is_cleared())
+            .collect();
+        output.sort_by(|a, b| a.status_type.as_str().cmp(b.status_type.as_str()));
+        output
+    }
+
+    /// The current status of `pubkey` of type `status_type` as of `now`, from a
+    /// set of events. See `current_all()`.
+    pub fn current(
+        events: &[Event],
+        pubkey: PublicKey,
+        status_type: &UserStatusType,
+        now: Unixtime,
+    ) -> Option<UserStatus> {
+        UserStatus::current_all(events, pubkey, now)
+            .into_iter()
+            .find(|s| s.status_type == *status_type)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_user_status() {
+        let private_key = PrivateKey::generate();
+        let pubkey = private_key.public_key();
+        let sign = |content: &str, status_type: UserStatusType, at: i64, exp: Option<i64>| {
+            let mut status = UserStatus::new(pubkey, status_type, content.to_owned());
+            status.created_at = Unixtime(at);
+            status.expiration = exp.map(Unixtime);
+            private_key.sign_event(status.to_pre_event()).unwrap()
+        };
+
+        let mut music = UserStatus::new(pubkey, UserStatusType::Music, "Song".to_owned());
+        music.link = Some(UncheckedUrl("spotify:track:abc".to_owned()));
+        music.expiration = Some(Unixtime(2000));
+        let event = private_key.sign_event(music.to_pre_event()).unwrap();
+        assert_eq!(UserStatus::from_event(&event).unwrap(), music);
+
+        let events = vec![
+            sign("Working", UserStatusType::General, 100, None),
+            sign("Lunch", UserStatusType::General, 200, Some(300)),
+            sign("Old song", UserStatusType::Music, 100, None),
+            sign("", UserStatusType::Music, 150, None),
+        ];
+
+        let now = Unixtime(250);
+        let current = UserStatus::current(&events, pubkey, &UserStatusType::General, now);
+        assert_eq!(current.unwrap().content, "Lunch");
+        // Cleared
+        assert!(UserStatus::current(&events, pubkey, &UserStatusType::Music, now).is_none());
+
+        // Expired; the earlier status does not come back
+        let now = Unixtime(300);
+        assert!(UserStatus::current_all(&events, pubkey, now).is_empty());
+    }
+}
@@ -0,0 +1,375 @@
+use crate::types::{
+    Event, EventKind, Id, NAddr, ParsedTag, PreEvent, PublicKey, RelayUrl, Tag, UncheckedUrl,
+    Unixtime,
+};
+use crate::Error;
+
+/// Normalize a topic into a NIP-54 'd' tag: letters are lowercased (non-ASCII
+/// letters are kept), whitespace becomes a '-', and other punctuation is dropped.
+/// Repeated '-'s are collapsed, and leading and trailing '-'s are trimmed.
+pub fn normalize_wiki_topic(topic: &str) -> String {
+    let mut d = String::with_capacity(topic.len());
+    for c in topic.chars() {
+        if c.is_alphanumeric() {
+            d.extend(c.to_lowercase());
+        } else if (c.is_whitespace() || c == '-') && !d.is_empty() && !d.ends_with('-') {
+            d.push('-');
+        }
+    }
+    d.trim_end_matches('-').to_owned()
+}
+
+/// A reference from one wiki article to another version of the article
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WikiRelation {
+    /// The other article
+    pub address: NAddr,
+
+    /// The particular version of the other article
+    pub version: Option<Id>,
+}
+
+impl WikiRelation {
+    fn tags(&self, marker: &str) -> Vec<Tag> {
+        let mut tags = vec![ParsedTag::Address {
+            address: self.address.clone(),
+            marker: Some(marker.to_owned()),
+        }
+        .into_tag()];
+        if let Some(version) = self.version {
+            tags.push(
+                ParsedTag::Event {
+                    id: version,
+                    recommended_relay_url: None,
+                    marker: Some(marker.to_owned()),
+                    author_pubkey: None,
+                }
+                .into_tag(),
+            );
+        }
+        tags
+    }
+}
+
+/// A NIP-54 wiki article (kind 30818)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WikiArticle {
+    /// The 'd' identifier, the normalized topic
+    pub d: String,
+
+    /// The title, if it differs from the topic
+    pub title: Option<String>,
+
+    /// A short summary
+    pub summary: Option<String>,
+
+    /// The article, in asciidoc
+    pub content: String,
+
+    /// The article this was forked from
+    pub fork_of: Option<WikiRelation>,
+
+    /// An article the author considers better than this one
+    pub defers_to: Option<WikiRelation>,
+}
+
+impl WikiArticle {
+    /// Create a new article on a topic. The 'd' tag is the normalized topic.
+    pub fn new(topic: &str, content: String) -> WikiArticle {
+        WikiArticle {
+            d: normalize_wiki_topic(topic),
+            content,
+            ..Default::default()
+        }
+    }
+
+    /// The address of this article, as published by `author`
+    pub fn address(&self, author: PublicKey) -> NAddr {
+        NAddr {
+            d: self.d.clone(),
+            relays: vec![],
+            kind: EventKind::WikiArticle,
+            author,
+        }
+    }
+
+    /// Fork an article: a copy of it, by a new author, that refers back to it
+    pub fn fork(article: &Event) -> Result<WikiArticle, Error> {
+        let original = WikiArticle::from_event(article)?;
+        Ok(WikiArticle {
+            fork_of: Some(WikiRelation {
+                address: original.address(article.pubkey),
+                version: Some(article.id),
+            }),
+            defers_to: None,
+            ..original
+        })
+    }
+
+    /// Parse a kind-30818 WikiArticle event
+    pub fn from_event(event: &Event) -> Result<WikiArticle, Error> {
+        if event.kind != EventKind::WikiArticle {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut article = WikiArticle {
+            content: event.content.clone(),
+            ..Default::default()
+        };
+        let mut fork_version: Option<Id> = None;
+        let mut defer_version: Option<Id> = None;
+        for tag in event.tags.iter() {
+            match tag.tagname() {
+                "d" => article.d = tag.value().to_owned(),
+                "title" => article.title = Some(tag.value().to_owned()),
+                "summary" => article.summary = Some(tag.value().to_owned()),
+                _ => match tag.parse() {
+                    Ok(ParsedTag::Address {
+                        address,
+                        marker: Some(marker),
+                    }) => {
+                        let relation = Some(WikiRelation {
+                            address,
+                            version: None,
+                        });
+                        match marker.as_str() {
+                            "fork" => article.fork_of = relation,
+                            "defer" => article.defers_to = relation,
+                            _ => {}
+                        }
+                    }
+                    Ok(ParsedTag::Event {
+                        id,
+                        marker: Some(marker),
+                        ..
+                    }) => match marker.as_str() {
+                        "fork" => fork_version = Some(id),
+                        "defer" => defer_version = Some(id),
+                        _ => {}
+                    },
+                    _ => {}
+                },
+            }
+        }
+        if let Some(relation) = article.fork_of.as_mut() {
+            relation.version = fork_version;
+        }
+        if let Some(relation) = article.defers_to.as_mut() {
+            relation.version = defer_version;
+        }
+        Ok(article)
+    }
+
+    /// Create the kind-30818 WikiArticle PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let mut tags = vec![Tag::new(&["d", &self.d])];
+        if let Some(title) = &self.title {
+            tags.push(Tag::new(&["title", title]));
+        }
+        if let Some(summary) = &self.summary {
+            tags.push(Tag::new(&["summary", summary]));
+        }
+        if let Some(relation) = &self.fork_of {
+            tags.extend(relation.tags("fork"));
+        }
+        if let Some(relation) = &self.defers_to {
+            tags.extend(relation.tags("defer"));
+        }
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::WikiArticle,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+
+    /// Choose which version of the article on `topic` to show, from `candidates`
+    /// (each with the relays it was seen on).
+    ///
+    /// Articles by `follows` (which should include the user) come first, then
+    /// articles seen on one of the user's `relays`, then everything else. Within
+    /// these, the newest article wins, and then the lowest id.
+    pub fn resolve<'a>(
+        topic: &str,
+        candidates: &'a [(Event, Vec<RelayUrl>)],
+        follows: &[PublicKey],
+        relays: &[RelayUrl],
+    ) -> Option<&'a Event> {
+        let d = normalize_wiki_topic(topic);
+        candidates
+            .iter()
+            .filter(|(event, _)| {
+                event.kind == EventKind::WikiArticle && event.parameter().as_ref() == Some(&d)
+            })
+            .min_by(|(a, a_relays), (b, b_relays)| {
+                let tier = |event: &Event, seen_on: &[RelayUrl]| {
+                    if follows.contains(&event.pubkey) {
+                        0
+                    } else if seen_on.iter().any(|r| relays.contains(r)) {
+                        1
+                    } else {
+                        2
+                    }
+                };
+                tier(a, a_relays)
+                    .cmp(&tier(b, b_relays))
+                    .then(b.created_at.cmp(&a.created_at))
+                    .then(a.id.cmp(&b.id))
+            })
+            .map(|(event, _)| event)
+    }
+}
+
+/// A NIP-54 request to merge one version of an article into another (kind 818)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WikiMergeRequest {
+    /// The article to merge into
+    pub destination: NAddr,
+
+    /// The version to merge
+    pub source: Id,
+
+    /// A relay where the version to merge may be found
+    pub source_relay: Option<UncheckedUrl>,
+
+    /// A message to the destination article's author
+    pub content: String,
+}
+
+impl WikiMergeRequest {
+    /// Create a request to merge the article version `source` into `destination`
+    pub fn new(destination: NAddr, source: &Event, content: String) -> WikiMergeRequest {
+        WikiMergeRequest {
+            destination,
+            source: source.id,
+            source_relay: None,
+            content,
+        }
+    }
+
+    /// Parse a kind-818 WikiMergeRequest event
+    pub fn from_event(event: &Event) -> Result<WikiMergeRequest, Error> {
+        if event.kind != EventKind::WikiMergeRequest {
+            return Err(Error::WrongEventKind);
+        }
+
+        let mut destination: Option<NAddr> = None;
+        let mut source: Option<(Id, Option<UncheckedUrl>)> = None;
+        for tag in event.tags.iter() {
+            match tag.parse() {
+                Ok(ParsedTag::Address { address, .. })
+                    if destination.is_none() && address.kind == EventKind::WikiArticle =>
+                {
+                    destination = Some(address);
+                }
+                Ok(ParsedTag::Event {
+                    id,
+                    recommended_relay_url,
+                    marker,
+                    ..
+                }) if marker.as_deref() == Some("source") => {
+                    source = Some((id, recommended_relay_url));
+                }
+                _ => {}
+            }
+        }
+
+        let (source, source_relay) = source.ok_or(Error::TagMismatch)?;
+        Ok(WikiMergeRequest {
+            destination: destination.ok_or(Error::TagMismatch)?,
+            source,
+            source_relay,
+            content: event.content.clone(),
+        })
+    }
+
+    /// Create the kind-818 WikiMergeRequest PreEvent
+    pub fn to_pre_event(&self, pubkey: PublicKey) -> PreEvent {
+        let tags = vec![
+            ParsedTag::Address {
+                address: self.destination.clone(),
+                marker: None,
+            }
+            .into_tag(),
+            ParsedTag::Event {
+                id: self.source,
+                recommended_relay_url: self.source_relay.clone(),
+                marker: Some("source".to_owned()),
+                author_pubkey: None,
+            }
+            .into_tag(),
+            Tag::new(&["p", &self.destination.author.as_hex_string()]),
+        ];
+
+        PreEvent {
+            pubkey,
+            created_at: Unixtime::now(),
+            kind: EventKind::WikiMergeRequest,
+            tags,
+            content: self.content.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PrivateKey, Signer};
+
+    #[test]
+    fn test_normalize_wiki_topic() {
+        assert_eq!(normalize_wiki_topic("Wiki Article"), "wiki-article");
+        assert_eq!(normalize_wiki_topic("NIP-54"), "nip-54");
+        assert_eq!(
+            normalize_wiki_topic("Ελληνικά Γράμματα"),
+            "ελληνικά-γράμματα"
+        );
+        assert_eq!(normalize_wiki_topic("What's up?"), "whats-up");
+        assert_eq!(normalize_wiki_topic("  Rock -- & Roll! "), "rock-roll");
+    }
+
+    #[test]
+    fn test_fork_and_merge_request() {
+        let alice = PrivateKey::generate();
+        let bob = PrivateKey::generate();
+
+        let mut original = WikiArticle::new("Bitcoin", "= Bitcoin".to_owned());
+        original.summary = Some("A currency".to_owned());
+        let original_event = alice
+            .sign_event(original.to_pre_event(alice.public_key()))
+            .unwrap();
+        assert_eq!(WikiArticle::from_event(&original_event).unwrap(), original);
+
+        let mut fork = WikiArticle::fork(&original_event).unwrap();
+        fork.content = "= Bitcoin\n\nA peer-to-peer currency".to_owned();
+        let fork_event = bob.sign_event(fork.to_pre_event(bob.public_key())).unwrap();
+        let parsed = WikiArticle::from_event(&fork_event).unwrap();
+        assert_eq!(parsed, fork);
+        assert_eq!(parsed.fork_of.unwrap().version, Some(original_event.id));
+
+        let request = WikiMergeRequest::new(
+            original.address(alice.public_key()),
+            &fork_event,
+            "Expanded the intro".to_owned(),
+        );
+        let request_event = bob
+            .sign_event(request.to_pre_event(bob.public_key()))
+            .unwrap();
+        assert_eq!(
+            WikiMergeRequest::from_event(&request_event).unwrap(),
+            request
+        );
+
+        // Bob follows only himself, so his fork is shown to him
+        let candidates = vec![
+            (original_event.clone(), vec![]),
+            (fork_event.clone(), vec![]),
+        ];
+        let shown = WikiArticle::resolve("bitcoin", &candidates, &[bob.public_key()], &[]);
+        assert_eq!(shown, Some(&fork_event));
+        let shown = WikiArticle::resolve("Bitcoin", &candidates, &[alice.public_key()], &[]);
+        assert_eq!(shown, Some(&original_event));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{Error, Event, Id, MilliSatoshi, PublicKey, Signer, UncheckedUrl};
+
+/// One recipient's share of a split zap (NIP-57 Appendix G)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZapSplit {
+    /// The recipient of this share
+    pub pubkey: PublicKey,
+
+    /// A relay where the recipient's metadata may be found
+    pub relay: Option<UncheckedUrl>,
+
+    /// The amount of this share
+    pub amount: MilliSatoshi,
+}
+
+/// A plan for dividing a zap among the recipients named in an event's 'zap' tags
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZapSplitPlan {
+    /// The event being zapped
+    pub zapped_event: Id,
+
+    /// The shares, in the order that the 'zap' tags appear in the event
+    pub splits: Vec<ZapSplit>,
+}
+
+impl ZapSplitPlan {
+    /// Plan how to divide `total` among the zap split recipients of `event`.
+    ///
+    /// Every share is first rounded down. The millisatoshis left over are then handed
+    /// out one at a time to the shares with the largest remainders (earlier tags win
+    /// ties), so the shares always add up to `total` exactly.
+    ///
+    /// Returns None if the event has no usable 'zap' tags.
+    pub fn new(event: &Event, total: MilliSatoshi) -> Option<ZapSplitPlan> {
+        let weights = event.zap_split_weights();
+        if weights.is_empty() {
+            return None;
+        }
+
+        let total_weight: u128 = weights.iter().map(|(_, _, w)| *w as u128).sum();
+        let total_msats: u128 = total.0 as u128;
+
+        let mut amounts: Vec<u128> = Vec::with_capacity(weights.len());
+        let mut remainders: Vec<(u128, usize)> = Vec::with_capacity(weights.len());
+        for (i, (_, _, w)) in weights.iter().enumerate() {
+            let scaled = total_msats * (*w as u128);
+            amounts.push(scaled / total_weight);
+            remainders.push((scaled % total_weight, i));
+        }
+
+        let assigned: u128 = amounts.iter().sum();
+        let leftover = (total_msats - assigned) as usize;
+
+        // Largest remainder first, then by tag order
+        remainders.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, i) in remainders.iter().take(leftover) {
+            amounts[*i] += 1;
+        }
+
+        let splits = weights
+            .into_iter()
+            .zip(amounts)
+            .map(|((pubkey, relay, _), amount)| ZapSplit {
+                pubkey,
+                relay,
+                amount: MilliSatoshi(amount as u64),
+            })
+            .collect();
+
+        Some(ZapSplitPlan {
+            zapped_event: event.id,
+            splits,
+        })
+    }
+
+    /// The total amount of the plan
+    pub fn total(&self) -> MilliSatoshi {
+        MilliSatoshi(self.splits.iter().map(|s| s.amount.0).sum())
+    }
+
+    /// Create a zap request event for each share that is not zero.
+    ///
+    /// These events are not published to nostr, each is sent to the lnurl of
+    /// the recipient of that share.
+    pub fn create_zap_requests<S>(
+        &self,
+        signer: &S,
+        relays: Vec<String>,
+        content: String,
+    ) -> Result<Vec<(ZapSplit, Event)>, Error>
+    where
+        S: Signer,
+    {
+        let mut output: Vec<(ZapSplit, Event)> = Vec::new();
+        for split in self.splits.iter() {
+            if split.amount.0 == 0 {
+                continue;
+            }
+            let event = signer.create_zap_request_event(
+                split.pubkey,
+                Some(self.zapped_event),
+                split.amount.0,
+                relays.clone(),
+                content.clone(),
+            )?;
+            output.push((split.clone(), event));
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{EventKind, ParsedTag, PreEvent, PrivateKey, Tag, Unixtime};
+
+    fn zap_tag(pubkey: PublicKey, weight: Option<u64>) -> Tag {
+        ParsedTag::Zap {
+            pubkey,
+            recommended_relay_url: None,
+            weight,
+        }
+        .into_tag()
+    }
+
+    #[test]
+    fn test_zap_split_plan() {
+        let private_key = PrivateKey::generate();
+        let recipients: Vec<PublicKey> = (0..3).map(|_| PublicKey::mock()).collect();
+
+        let pre_event = PreEvent {
+            pubkey: private_key.public_key(),
+            created_at: Unixtime::mock(),
+            kind: EventKind::TextNote,
+            tags: recipients.iter().map(|pk| zap_tag(*pk, Some(1))).collect(),
+            content: "split me".to_owned(),
+        };
+        let event = private_key.sign_event(pre_event).unwrap();
+
+        let plan = ZapSplitPlan::new(&event, MilliSatoshi(100)).unwrap();
+        let amounts: Vec<u64> = plan.splits.iter().map(|s| s.amount.0).collect();
+        assert_eq!(amounts, vec![34, 33, 33]);
+        assert_eq!(plan.total(), MilliSatoshi(100));
+
+        let requests = plan
+            .create_zap_requests(&private_key, vec![], "".to_owned())
+            .unwrap();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[0].1.kind, EventKind::ZapRequest);
+    }
+
+    #[test]
+    fn test_zap_split_partial_weights() {
+        let private_key = PrivateKey::generate();
+        let a = PublicKey::mock();
+        let b = PublicKey::mock();
+        let c = PublicKey::mock();
+
+        let pre_event = PreEvent {
+            pubkey: private_key.public_key(),
+            created_at: Unixtime::mock(),
+            kind: EventKind::TextNote,
+            tags: vec![zap_tag(a, Some(1)), zap_tag(b, None), zap_tag(c, Some(2))],
+            content: "split me".to_owned(),
+        };
+        let event = private_key.sign_event(pre_event).unwrap();
+
+        let percentages = event.zap_splits();
+        assert_eq!(percentages.len(), 2);
+        assert_eq!(percentages[0].0, a);
+        assert_eq!(percentages[1].0, c);
+
+        let plan = ZapSplitPlan::new(&event, MilliSatoshi(1001)).unwrap();
+        assert_eq!(plan.splits[0].amount, MilliSatoshi(334));
+        assert_eq!(plan.splits[1].amount, MilliSatoshi(667));
+    }
+
+    #[test]
+    fn test_zap_split_huge_and_bad_weights() {
+        let private_key = PrivateKey::generate();
+        let a = PublicKey::mock();
+        let b = PublicKey::mock();
+        let c = PublicKey::mock();
+
+        let mut bad = zap_tag(c, None);
+        bad.set_index(3, "1.5".to_owned());
+        let pre_event = PreEvent {
+            pubkey: private_key.public_key(),
+            created_at: Unixtime::mock(),
+            kind: EventKind::TextNote,
+            tags: vec![zap_tag(a, Some(u64::MAX)), zap_tag(b, Some(u64::MAX)), bad],
+            content: "split me".to_owned(),
+        };
+        let event = private_key.sign_event(pre_event).unwrap();
+
+        // The unparsable weight counts as missing, so that recipient is dropped
+        let percentages = event.zap_splits();
+        assert_eq!(percentages.len(), 2);
+        assert_eq!(percentages[0], (a, 50.0));
+        assert_eq!(percentages[1], (b, 50.0));
+    }
+}
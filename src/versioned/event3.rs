@@ -1,8 +1,8 @@
 use super::TagV3;
 use crate::types::{
-    EventDelegation, EventKind, EventReference, FileMetadata, Id, KeySigner, MilliSatoshi,
-    NostrBech32, NostrUrl, ParsedTag, PrivateKey, PublicKey, RelayUrl, Signature, Signer, Unixtime,
-    ZapData,
+    EventDelegation, EventKind, EventReference, FileMetadata, Id, IdentityPlatform, KeySigner,
+    MilliSatoshi, NostrBech32, NostrUrl, ParsedTag, PrivateKey, PublicKey, RelayUrl, Signature,
+    Signer, UncheckedUrl, Unixtime, ZapData,
 };
 use crate::{Error, IntoVec};
 use lightning_invoice::Bolt11Invoice;
@@ -838,6 +838,51 @@ impl EventV3 {
         }))
     }
 
+    /// If this event has NIP-57 'zap' tags, get the zap split recipients along with
+    /// their relay hints and weights.
+    ///
+    /// A weight that is not a whole number is treated as missing. If only some of the
+    /// tags carry a weight, the ones without a weight are dropped. If none of them
+    /// carry a weight, every recipient gets an equal weight of 1. Recipients with a
+    /// weight of zero are dropped.
+    pub fn zap_split_weights(&self) -> Vec<(PublicKey, Option<UncheckedUrl>, u64)> {
+        let mut tagged: Vec<(PublicKey, Option<UncheckedUrl>, Option<u64>)> = Vec::new();
+
+        for tag in self.tags.iter() {
+            if let Ok(ParsedTag::Zap {
+                pubkey,
+                recommended_relay_url,
+                weight,
+            }) = tag.parse()
+            {
+                tagged.push((pubkey, recommended_relay_url, weight));
+            }
+        }
+
+        let any_weighted = tagged.iter().any(|(_, _, w)| w.is_some());
+
+        tagged
+            .drain(..)
+            .filter_map(|(pk, rru, w)| match w {
+                Some(0) => None,
+                Some(w) => Some((pk, rru, w)),
+                None if any_weighted => None,
+                None => Some((pk, rru, 1)),
+            })
+            .collect()
+    }
+
+    /// If this event has NIP-57 'zap' tags, get the percentage of a zap that each
+    /// recipient should receive. These add up to 100.
+    pub fn zap_splits(&self) -> Vec<(PublicKey, f64)> {
+        let weights = self.zap_split_weights();
+        let total: u128 = weights.iter().map(|(_, _, w)| *w as u128).sum();
+        weights
+            .iter()
+            .map(|(pk, _, w)| (*pk, *w as f64 * 100.0 / total as f64))
+            .collect()
+    }
+
     /// If this event specifies the client that created it, return that client string
     pub fn client(&self) -> Option<String> {
         for tag in self.tags.iter() {
@@ -917,6 +962,46 @@ impl EventV3 {
         }
     }
 
+    /// The NIP-40 expiration time of this event, if it has a valid one
+    pub fn expiration(&self) -> Option<Unixtime> {
+        self.tags
+            .iter()
+            .filter(|tag| tag.tagname() == "expiration")
+            .find_map(|tag| match tag.parse() {
+                Ok(ParsedTag::Expiration(t)) => Some(t),
+                _ => None,
+            })
+    }
+
+    /// The NIP-29 group this event belongs to (its 'h' tag), if any
+    pub fn group_id(&self) -> Option<String> {
+        for tag in self.tags.iter() {
+            if let Ok(ParsedTag::Group(id)) = tag.parse() {
+                return Some(id);
+            }
+        }
+        None
+    }
+
+    /// Return the NIP-39 external identities claimed in this event, as
+    /// (platform, identity, proof)
+    pub fn external_identities(&self) -> Vec<(IdentityPlatform, String, String)> {
+        let mut output: Vec<(IdentityPlatform, String, String)> = Vec::new();
+
+        for tag in self.tags.iter() {
+            if let Ok(ParsedTag::ExternalIdentity {
+                platform,
+                identity,
+                proof,
+            }) = tag.parse()
+            {
+                output.push((platform, identity, proof));
+            }
+        }
+
+        output
+    }
+
     /// Return all the hashtags this event refers to
     pub fn hashtags(&self) -> Vec<String> {
         if !self.kind.is_feed_displayable() {
@@ -1237,7 +1322,7 @@ impl TryFrom<PreEventV3> for RumorV3 {
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::types::{DelegationConditions, Signer, UncheckedUrl};
+    use crate::types::{DelegationConditions, Signer};
 
     test_serde! {EventV3, test_event_serde}
 
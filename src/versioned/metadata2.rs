@@ -1,3 +1,5 @@
+use crate::types::{Birthday, MetadataIssue, Nip05Identifier, Url};
+use crate::Error;
 use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
 use serde::ser::{Serialize, SerializeMap, Serializer};
 use serde_json::{json, Map, Value};
@@ -87,6 +89,280 @@ impl MetadataV2 {
 
         None
     }
+
+    fn get_str(&self, key: &str) -> Option<&str> {
+        match self.other.get(key) {
+            Some(Value::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn set_str(&mut self, key: &str, value: Option<String>) {
+        match value {
+            Some(v) => {
+                let _ = self.other.insert(key.to_owned(), Value::String(v));
+            }
+            None => {
+                let _ = self.other.remove(key);
+            }
+        }
+    }
+
+    /// The display name (NIP-24), falling back to the deprecated `displayName`
+    pub fn display_name(&self) -> Option<&str> {
+        self.get_str("display_name")
+            .or_else(|| self.get_str("displayName"))
+    }
+
+    /// Set the display name (NIP-24). This also removes the deprecated `displayName`.
+    pub fn set_display_name(&mut self, display_name: Option<String>) {
+        let _ = self.other.remove("displayName");
+        self.set_str("display_name", display_name);
+    }
+
+    /// The website (NIP-24)
+    pub fn website(&self) -> Option<&str> {
+        self.get_str("website")
+    }
+
+    /// Set the website (NIP-24). It must be a valid URL.
+    pub fn set_website(&mut self, website: Option<&str>) -> Result<(), Error> {
+        let website = website.map(Url::try_from_str).transpose()?;
+        self.set_str("website", website.map(|u| u.as_str().to_owned()));
+        Ok(())
+    }
+
+    /// The banner image URL (NIP-24)
+    pub fn banner(&self) -> Option<&str> {
+        self.get_str("banner")
+    }
+
+    /// Set the banner image URL (NIP-24). It must be a valid URL.
+    pub fn set_banner(&mut self, banner: Option<&str>) -> Result<(), Error> {
+        let banner = banner.map(Url::try_from_str).transpose()?;
+        self.set_str("banner", banner.map(|u| u.as_str().to_owned()));
+        Ok(())
+    }
+
+    /// Whether the account is automated (NIP-24). None if unset or not a boolean.
+    pub fn bot(&self) -> Option<bool> {
+        self.other.get("bot").and_then(|v| v.as_bool())
+    }
+
+    /// Set whether the account is automated (NIP-24)
+    pub fn set_bot(&mut self, bot: Option<bool>) {
+        match bot {
+            Some(b) => {
+                let _ = self.other.insert("bot".to_owned(), Value::Bool(b));
+            }
+            None => {
+                let _ = self.other.remove("bot");
+            }
+        }
+    }
+
+    /// The birthday (NIP-24). None if unset or malformed.
+    pub fn birthday(&self) -> Option<Birthday> {
+        self.other
+            .get("birthday")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+    }
+
+    /// Set the birthday (NIP-24). The month and day must be in range.
+    pub fn set_birthday(&mut self, birthday: Option<Birthday>) -> Result<(), Error> {
+        match birthday {
+            Some(b) => {
+                if !b.is_valid() {
+                    return Err(Error::InvalidMetadata("birthday out of range".to_owned()));
+                }
+                let _ = self
+                    .other
+                    .insert("birthday".to_owned(), serde_json::to_value(b)?);
+            }
+            None => {
+                let _ = self.other.remove("birthday");
+            }
+        }
+        Ok(())
+    }
+
+    /// The LNURL (NIP-57), bech32 encoded
+    pub fn lud06(&self) -> Option<&str> {
+        self.get_str("lud06")
+    }
+
+    /// Set the LNURL (NIP-57). It must be a bech32 `lnurl`.
+    pub fn set_lud06(&mut self, lud06: Option<String>) -> Result<(), Error> {
+        if let Some(l) = &lud06 {
+            if !valid_lud06(l) {
+                return Err(Error::InvalidMetadata(format!(
+                    "lud06 is not an lnurl: {l}"
+                )));
+            }
+        }
+        self.set_str("lud06", lud06);
+        Ok(())
+    }
+
+    /// The lightning address (NIP-57)
+    pub fn lud16(&self) -> Option<&str> {
+        self.get_str("lud16")
+    }
+
+    /// Set the lightning address (NIP-57). It must look like `user@domain`.
+    pub fn set_lud16(&mut self, lud16: Option<String>) -> Result<(), Error> {
+        if let Some(l) = &lud16 {
+            if !valid_lud16(l) {
+                return Err(Error::InvalidMetadata(format!(
+                    "lud16 is not a lightning address: {l}"
+                )));
+            }
+        }
+        self.set_str("lud16", lud16);
+        Ok(())
+    }
+
+    /// List the problems with the metadata. An empty list means it is valid.
+    pub fn validate(&self) -> Vec<MetadataIssue> {
+        let mut issues: Vec<MetadataIssue> = Vec::new();
+        let mut issue = |field: &str, problem: &str| {
+            issues.push(MetadataIssue {
+                field: field.to_owned(),
+                problem: problem.to_owned(),
+            })
+        };
+
+        if let Some(picture) = &self.picture {
+            if Url::try_from_str(picture).is_err() {
+                issue("picture", "not a valid URL");
+            }
+        }
+        if let Some(nip05) = &self.nip05 {
+            if Nip05Identifier::try_from_str(nip05).is_err() {
+                issue("nip05", "not a valid NIP-05 identifier");
+            }
+        }
+        for key in ["website", "banner"] {
+            match self.other.get(key) {
+                None | Some(Value::Null) => {}
+                Some(Value::String(s)) if Url::try_from_str(s).is_ok() => {}
+                Some(_) => issue(key, "not a valid URL"),
+            }
+        }
+        for key in ["display_name", "lud06", "lud16"] {
+            match self.other.get(key) {
+                None | Some(Value::Null) | Some(Value::String(_)) => {}
+                Some(_) => issue(key, "not a string"),
+            }
+        }
+        if let Some(l) = self.lud06() {
+            if !valid_lud06(l) {
+                issue("lud06", "not a bech32 lnurl");
+            }
+        }
+        if let Some(l) = self.lud16() {
+            if !valid_lud16(l) {
+                issue("lud16", "not a lightning address");
+            }
+        }
+        match self.other.get("bot") {
+            None | Some(Value::Null) | Some(Value::Bool(_)) => {}
+            Some(_) => issue("bot", "not a boolean"),
+        }
+        match self.other.get("birthday") {
+            None | Some(Value::Null) => {}
+            Some(_) => match self.birthday() {
+                Some(b) if b.is_valid() => {}
+                Some(_) => issue("birthday", "out of range"),
+                None => issue("birthday", "not a birthday object"),
+            },
+        }
+        if self.other.contains_key("displayName") {
+            issue("displayName", "deprecated, use display_name");
+        }
+
+        issues
+    }
+
+    /// A copy suitable for display: strings are trimmed and cut to `max_chars`
+    /// characters (`max_about_chars` for `about`), line breaks are removed from
+    /// single-line fields, and fields that fail validation are dropped. URLs,
+    /// NIP-05 identifiers and lightning addresses longer than `max_chars` are
+    /// dropped rather than cut, since a cut one could point somewhere else.
+    pub fn sanitized(&self, max_chars: usize, max_about_chars: usize) -> MetadataV2 {
+        fn clean(s: &str, max: usize, single_line: bool) -> Option<String> {
+            let s: String = s.trim().chars().take(max).collect();
+            let s = if single_line {
+                s.split_whitespace().collect::<Vec<&str>>().join(" ")
+            } else {
+                s.trim_end().to_owned()
+            };
+            if s.is_empty() {
+                None
+            } else {
+                Some(s)
+            }
+        }
+
+        // Trimmed and on one line, or None if too long to show whole
+        let whole = |s: &str| {
+            if s.trim().chars().count() > max_chars {
+                None
+            } else {
+                clean(s, max_chars, true)
+            }
+        };
+        let url = |s: Option<&str>| s.and_then(whole).filter(|u| Url::try_from_str(u).is_ok());
+
+        let mut m = MetadataV2::new();
+        m.name = self.name.as_ref().and_then(|s| clean(s, max_chars, true));
+        m.about = self
+            .about
+            .as_ref()
+            .and_then(|s| clean(s, max_about_chars, false));
+        m.picture = url(self.picture.as_deref());
+        m.nip05 = self
+            .nip05
+            .as_deref()
+            .and_then(whole)
+            .and_then(|s| Nip05Identifier::try_from_str(&s).ok())
+            .map(|id| id.to_string());
+        m.fields = self
+            .fields
+            .iter()
+            .filter_map(|(k, v)| Some((clean(k, max_chars, true)?, clean(v, max_chars, true)?)))
+            .collect();
+
+        m.set_display_name(self.display_name().and_then(|s| clean(s, max_chars, true)));
+        let _ = m.set_website(url(self.website()).as_deref());
+        let _ = m.set_banner(url(self.banner()).as_deref());
+        m.set_bot(self.bot());
+        let _ = m.set_birthday(self.birthday().filter(|b| b.is_valid()));
+        let _ = m.set_lud06(self.lud06().and_then(whole).filter(|l| valid_lud06(l)));
+        let _ = m.set_lud16(self.lud16().and_then(whole).filter(|l| valid_lud16(l)));
+
+        m
+    }
+}
+
+fn valid_lud06(lud06: &str) -> bool {
+    match bech32::decode(lud06) {
+        Ok(data) => data.0 == *crate::HRP_LNURL,
+        Err(_) => false,
+    }
+}
+
+// A lightning address looks like an email address
+fn valid_lud16(lud16: &str) -> bool {
+    match lud16.split_once('@') {
+        Some((user, domain)) => {
+            !user.is_empty()
+                && !user.contains(char::is_whitespace)
+                && !domain.contains('@')
+                && Nip05Identifier::try_from_str(domain).is_ok()
+        }
+        None => false,
+    }
 }
 
 impl Serialize for MetadataV2 {
@@ -233,4 +509,81 @@ mod test {
         assert_eq!(m.fields[0], ("Pronouns".to_string(), "ye/haw".to_string()));
         assert_eq!(m.fields[2], ("Color".to_string(), "green".to_string()));
     }
+
+    #[test]
+    fn test_metadata_typed_fields() {
+        let mut m = MetadataV2::new();
+        m.set_display_name(Some("Bob".to_owned()));
+        m.set_website(Some("https://bob.example.com")).unwrap();
+        assert!(m.set_website(Some("not a url")).is_err());
+        assert!(m.set_banner(Some("/banner.png")).is_err());
+        m.set_bot(Some(true));
+        m.set_lud16(Some("bob@wallet.example.com".to_owned()))
+            .unwrap();
+        assert!(m.set_lud16(Some("bob".to_owned())).is_err());
+        assert!(m.set_lud06(Some("lnbc1".to_owned())).is_err());
+        assert!(m
+            .set_birthday(Some(Birthday {
+                year: None,
+                month: Some(13),
+                day: None
+            }))
+            .is_err());
+        let date = |year, month, day| Birthday {
+            year,
+            month: Some(month),
+            day: Some(day),
+        };
+        assert!(!date(None, 4, 31).is_valid());
+        assert!(date(None, 2, 29).is_valid());
+        assert!(date(Some(2024), 2, 29).is_valid());
+        assert!(!date(Some(2023), 2, 29).is_valid());
+        assert!(!date(Some(1900), 2, 29).is_valid());
+        assert!(date(Some(2000), 2, 29).is_valid());
+
+        let json = serde_json::to_string(&m).unwrap();
+        let m2: MetadataV2 = serde_json::from_str(&json).unwrap();
+        assert_eq!(m2.display_name(), Some("Bob"));
+        assert_eq!(m2.website(), Some("https://bob.example.com/"));
+        assert_eq!(m2.bot(), Some(true));
+        assert!(m2.validate().is_empty());
+    }
+
+    #[test]
+    fn test_metadata_validate_and_sanitize() {
+        let json = r##"{"name":"  Mallory\nthe\tgreat  ","about":"hi","picture":"nonsense","nip05":"bad nip05","bot":"yes","website":"https://ok.example.com","lud16":"no-at-sign","birthday":{"month":2,"day":30}}"##;
+        let m: MetadataV2 = serde_json::from_str(json).unwrap();
+        let fields: Vec<String> = m.validate().into_iter().map(|i| i.field).collect();
+        assert_eq!(fields, vec!["picture", "nip05", "lud16", "bot", "birthday"]);
+
+        let s = m.sanitized(8, 100);
+        assert_eq!(s.name.as_deref(), Some("Mallory"));
+        assert_eq!(s.picture, None);
+        assert_eq!(s.nip05, None);
+        assert_eq!(s.bot(), None);
+        assert_eq!(s.lud16(), None);
+        assert_eq!(s.birthday(), None);
+        assert_eq!(s.website(), None);
+        assert!(s.validate().is_empty());
+
+        // Links are trimmed, and dropped if too long to show whole
+        let long = "a".repeat(100_000);
+        let json = format!(
+            r##"{{"picture":"  https://example.com/p.png\n","website":"https://example.com/{long}","banner":" https://example.com/b.png ","lud16":"  bob@wallet.example.com ","nip05":" bob@example.com "}}"##
+        );
+        let m: MetadataV2 = serde_json::from_str(&json).unwrap();
+        let s = m.sanitized(30, 100);
+        assert_eq!(s.picture.as_deref(), Some("https://example.com/p.png"));
+        assert_eq!(s.website(), None);
+        assert_eq!(s.banner(), Some("https://example.com/b.png"));
+        assert_eq!(s.lud16(), Some("bob@wallet.example.com"));
+        assert_eq!(s.nip05.as_deref(), Some("bob@example.com"));
+        let m: MetadataV2 = serde_json::from_str(&format!(
+            r##"{{"picture":"https://example.com/{long}","lud16":"{long}@wallet.example.com"}}"##
+        ))
+        .unwrap();
+        let s = m.sanitized(30, 100);
+        assert_eq!(s.picture, None);
+        assert_eq!(s.lud16(), None);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::types::{PublicKeyHex, UncheckedUrl};
+use crate::types::{Nip05Identifier, PublicKey, PublicKeyHex, RelayUrl, UncheckedUrl};
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "speedy")]
 use speedy::{Readable, Writable};
@@ -19,6 +19,39 @@ pub struct Nip05V1 {
 }
 
 impl Nip05V1 {
+    /// Look up the public key for an identifier. An exact match is preferred, then
+    /// names match case-insensitively (the first such name in sorted order wins).
+    pub fn lookup(&self, identifier: &Nip05Identifier) -> Option<PublicKey> {
+        let pkh = match self.names.get(&identifier.local) {
+            Some(pkh) => pkh,
+            None => self
+                .names
+                .iter()
+                .filter(|(name, _)| name.to_lowercase() == identifier.local)
+                .min_by(|a, b| a.0.cmp(b.0))
+                .map(|(_, pkh)| pkh)?,
+        };
+        PublicKey::try_from_hex_string(pkh.as_str(), true).ok()
+    }
+
+    /// Whether this document (fetched from the identifier's well-known URL) says
+    /// the identifier belongs to `pubkey`
+    pub fn verify(&self, identifier: &Nip05Identifier, pubkey: &PublicKey) -> bool {
+        self.lookup(identifier) == Some(*pubkey)
+    }
+
+    /// The relays published for `pubkey`. Invalid relay URLs are skipped.
+    pub fn relays_for(&self, pubkey: &PublicKey) -> Vec<RelayUrl> {
+        let pkh: PublicKeyHex = pubkey.into();
+        match self.relays.get(&pkh) {
+            Some(urls) => urls
+                .iter()
+                .filter_map(|u| RelayUrl::try_from_unchecked_url(u).ok())
+                .collect(),
+            None => vec![],
+        }
+    }
+
     // Mock data for testing
     #[allow(dead_code)]
     pub(crate) fn mock() -> Nip05V1 {
@@ -78,4 +111,33 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn test_nip05_verify() {
+        let nip05 = Nip05V1::mock();
+        let bob = PublicKey::try_from_hex_string(
+            "b0635d6a9851d3aed0cd6c495b282167acf761729078d975fc341b22650b07b9",
+            true,
+        )
+        .unwrap();
+
+        let id = Nip05Identifier::try_from_str("BOB@example.com").unwrap();
+        assert!(nip05.verify(&id, &bob));
+        assert!(!nip05.verify(&id, &PublicKey::mock()));
+
+        let root = Nip05Identifier::try_from_str("example.com").unwrap();
+        assert!(!nip05.verify(&root, &bob));
+
+        assert_eq!(nip05.relays_for(&bob).len(), 2);
+
+        // An exact-case name wins over names that differ only by case
+        let mut nip05 = nip05;
+        let other = PublicKey::mock();
+        let _ = nip05.names.insert("Bob".to_owned(), other.into());
+        let _ = nip05.names.insert("BOB".to_owned(), other.into());
+        let id = Nip05Identifier::try_from_str("bob@example.com").unwrap();
+        assert!(nip05.verify(&id, &bob));
+        let _ = nip05.names.remove("bob");
+        assert!(nip05.verify(&id, &other));
+    }
 }